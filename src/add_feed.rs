@@ -0,0 +1,69 @@
+use crate::discovery::Candidate;
+
+// Dialog to subscribe to a typed or pasted URL.
+#[derive(Debug, Default)]
+pub struct AddFeed {
+    pub url: String,
+    pub check: Check,
+    // Index of the highlighted feed if several were found.
+    pub selected: usize,
+}
+
+// Whether the URL in the dialog points to a feed.
+#[derive(Debug, Default, PartialEq)]
+pub enum Check {
+    #[default]
+    Unchecked,
+    Checking,
+    // The URL is a feed or a page announcing the feeds.
+    Found(Vec<Candidate>),
+    Failed(String),
+}
+
+impl AddFeed {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            check: Check::Unchecked,
+            selected: 0,
+        }
+    }
+
+    // Feed that gets subscribed to on confirmation.
+    pub fn selected_candidate(&self) -> Option<&Candidate> {
+        match &self.check {
+            Check::Found(candidates) => candidates.get(self.selected),
+            _ => None,
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if let Check::Found(candidates) = &self.check {
+            self.selected = (self.selected + candidates.len() - 1) % candidates.len();
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if let Check::Found(candidates) = &self.check {
+            self.selected = (self.selected + 1) % candidates.len();
+        }
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.url.push_str(text);
+        self.check = Check::Unchecked;
+    }
+
+    pub fn pop(&mut self) {
+        self.url.pop();
+        self.check = Check::Unchecked;
+    }
+}
+
+// First line of pasted text, pasted URLs often come with a line break.
+pub fn pasted_url(text: &str) -> &str {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+}
@@ -1,80 +1,409 @@
-use crate::feed::{check_url, Feed};
-use ratatui::widgets::ListState;
+use crate::add_feed::{AddFeed, Check};
+use crate::article::{Article, ArticleSet, Enclosure};
+use crate::cache::FeedCache;
+use crate::config::Config;
+use crate::discovery::{discover, Candidate};
+use crate::download::{self, DownloadEvent, Downloads};
+use crate::export::{self, Query};
+use crate::feed::{
+    check_url, fetch_and_parse_feeds, parse_feed, FeedStatus, FeedUpdate, FetchError,
+};
+use crate::fetcher::{Fetcher, Progress};
+use crate::html::Document;
+use crate::opml;
+use crate::paths::{write_file, Paths};
+use crate::reader::Reader;
+use crate::scheduler::Scheduler;
+use crate::search::Search;
+use crate::sidebar::{self, Filter, SidebarEntry};
+use crate::store::{Retention, Store};
+use crate::subscription::{normalize_folder, Subscription};
+use crate::theme::Theme;
+use futures::future::join_all;
+use ratatui::layout::Rect;
+use ratatui::widgets::{ListState, TableState};
 use ratatui_image::{picker::Picker, protocol::StatefulProtocol};
-use rustc_hash::FxHashMap;
-use std::{env, error, fs};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use std::{error, fs, path::Path, time::Instant};
 use tokio::sync::mpsc;
 
+// How long a notice stays in the status bar.
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
+
 // Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 pub type ImageData = (String, Box<dyn StatefulProtocol>);
+// URL checked for the add feed dialog and the feeds found there or the reason there are none.
+pub type UrlCheck = (String, anyhow::Result<Vec<Candidate>>);
 
 // Application.
 pub struct App {
     pub running: bool,
     pub list_state: ListState,
-    pub feeds: Vec<Feed>,
+    pub articles: Vec<Article>,
+    // Indices into `articles` of the entries shown in the list, `list_state` refers to these.
+    pub visible: Vec<usize>,
+    pub sidebar_state: ListState,
+    // Entries of the sidebar, `sidebar_state` refers to these.
+    pub sidebar: Vec<SidebarEntry>,
+    pub sidebar_visible: bool,
+    pub focus: Focus,
+    // Feed or folder whose articles are shown, all articles if `None`.
+    pub filter: Option<Filter>,
+    // Search narrowing down the list, if one was started.
+    pub search: Option<Search>,
     pub app_state: AppState,
-    pub feed_urls: Vec<String>,
-    pub feed_receiver: mpsc::Receiver<Feed>,
+    pub subscriptions: Vec<Subscription>,
+    pub feed_receiver: mpsc::Receiver<FeedUpdate>,
+    pub feed_sender: mpsc::Sender<FeedUpdate>,
+    pub scheduler: Scheduler,
+    pub fetcher: Fetcher,
+    // Feeds fetched of those that became due, shown in the status bar.
+    pub fetch_progress: Progress,
+    pub feed_cache: FeedCache,
+    pub feed_status: FxHashMap<String, FeedStatus>,
+    pub feeds_table_state: TableState,
     pub image_receiver: mpsc::Receiver<ImageData>,
     pub image_sender: mpsc::Sender<ImageData>,
     pub current_feed_image: Option<Box<dyn StatefulProtocol>>,
+    // Description of the article in the popup, converted once when the popup opens.
+    pub current_description: Document,
     pub cached_images: FxHashMap<String, Box<dyn StatefulProtocol>>,
+    pub check_receiver: mpsc::Receiver<UrlCheck>,
+    pub check_sender: mpsc::Sender<UrlCheck>,
+    pub read_articles: ArticleSet,
+    pub starred_articles: ArticleSet,
+    pub store: Store,
+    pub paths: Paths,
+    pub config: Config,
+    pub areas: Areas,
+    // Time and position of the last click, to detect double clicks.
+    pub last_click: Option<(Instant, u16, u16)>,
+    pub downloads: Downloads,
+    pub notice: Option<Notice>,
+}
+
+// Short message in the status bar, e.g. about a finished download.
+#[derive(Debug)]
+pub struct Notice {
+    pub text: String,
+    pub error: bool,
+    pub shown: Instant,
 }
 
 #[derive(Debug)]
 pub enum AppState {
-    Popup(Feed),
+    Popup(Box<Article>),
+    Reader(Box<Reader>),
+    List,
+    Feeds,
+    AddFeed(Box<AddFeed>),
+}
+
+// Screen areas of the last rendered frame, to find out what the mouse points at.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Areas {
+    pub list: Rect,
+    pub sidebar: Rect,
+    pub popup: Rect,
+    pub feeds: Rect,
+}
+
+// Pane that receives the navigation keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Focus {
+    Sidebar,
     List,
-    PastedLink(String),
 }
 
 impl App {
-    pub async fn new() -> Self {
-        let (tx, rx) = mpsc::channel::<Feed>(20);
+    pub async fn new(paths: Paths, config: Config) -> anyhow::Result<Self> {
+        let (tx, rx) = mpsc::channel::<FeedUpdate>(20);
         let (img_tx, img_rx) = mpsc::channel::<ImageData>(1);
-        let feed_urls = Self::load();
+        let (check_tx, check_rx) = mpsc::channel::<UrlCheck>(1);
+        let subscriptions = Self::load(&paths);
+
+        let fetcher = Fetcher::new(config.fetch)?;
+        let feed_cache = FeedCache::new(paths.feed_cache_dir());
+        let store =
+            Store::open(&paths.database_file(), &paths.read_articles_file()).map_err(|e| {
+                anyhow::anyhow!("Failed to open {}: {}", paths.database_file().display(), e)
+            })?;
 
-        for url in feed_urls.iter() {
-            Feed::fetch_and_parse_feeds(url, &tx);
+        // All feeds are due right away and get fetched on the first tick
+        let mut scheduler = Scheduler::new(config.refresh_interval());
+        let mut feed_status = FxHashMap::default();
+        for subscription in subscriptions.iter() {
+            scheduler.add(subscription);
+            feed_status.insert(
+                subscription.url.clone(),
+                FeedStatus {
+                    title: subscription.title.clone(),
+                    last_success: feed_cache.last_success(&subscription.url),
+                    ..Default::default()
+                },
+            );
         }
 
-        Self {
+        Ok(Self {
             running: true,
             list_state: ListState::default(),
-            feeds: Vec::with_capacity(feed_urls.len() * 10),
+            articles: Vec::with_capacity(subscriptions.len() * 10),
+            visible: Vec::new(),
+            sidebar_state: ListState::default().with_selected(Some(0)),
+            sidebar: sidebar::entries(&subscriptions),
+            sidebar_visible: config.sidebar,
+            focus: Focus::List,
+            filter: None,
+            search: None,
             app_state: AppState::List,
-            feed_urls,
+            subscriptions,
             feed_receiver: rx,
+            feed_sender: tx,
+            scheduler,
+            downloads: Downloads::new(fetcher.client().clone()),
+            fetcher,
+            fetch_progress: Progress::default(),
+            feed_cache,
+            feed_status,
+            feeds_table_state: TableState::default(),
             image_receiver: img_rx,
             image_sender: img_tx,
             current_feed_image: None,
+            current_description: Document::default(),
             cached_images: FxHashMap::default(),
-        }
+            check_receiver: check_rx,
+            check_sender: check_tx,
+            read_articles: store.read_articles()?,
+            starred_articles: store.starred_articles()?,
+            store,
+            paths,
+            config,
+            areas: Areas::default(),
+            last_click: None,
+            notice: None,
+        })
     }
 
     pub fn tick(&mut self) {
-        while let Ok(feed) = self.feed_receiver.try_recv() {
-            if let Err(pos) = self.feeds.binary_search(&feed) {
-                self.feeds.insert(pos, feed);
-            }
+        let due = self.scheduler.due();
+        self.fetch_progress.started(due.len());
+        for url in due {
+            fetch_and_parse_feeds(&self.fetcher, &url, &self.feed_sender, &self.feed_cache);
         }
 
-        if self.list_state.selected().is_none() && !self.feeds.is_empty() {
+        let mut updates = Vec::new();
+        while let Ok(update) = self.feed_receiver.try_recv() {
+            updates.push(update);
+        }
+        if !updates.is_empty() {
+            self.fetch_progress.finished(updates.len());
+            self.merge_updates(updates);
+        }
+
+        if self.list_state.selected().is_none() && !self.visible.is_empty() {
             self.list_state.select(Some(0));
         }
 
         if let Ok((url, image)) = self.image_receiver.try_recv() {
-            if let AppState::Popup(feed) = &self.app_state {
-                if let Some(feed_url) = feed.get_image_url() {
-                    if feed_url == url {
+            if let AppState::Popup(article) = &self.app_state {
+                if let Some(image_url) = article.image_url() {
+                    if image_url == url {
                         self.current_feed_image = Some(image.clone());
                     }
                 }
             }
             self.cached_images.insert(url, image);
         }
+
+        for event in self.downloads.poll() {
+            match event {
+                DownloadEvent::Finished { path, .. } => {
+                    self.notify(format!("Downloaded {}", path.display()), false)
+                }
+                DownloadEvent::Failed { url, error } => {
+                    self.notify(format!("Download of {} failed: {}", url, error), true)
+                }
+                DownloadEvent::Progress { .. } => {}
+            }
+        }
+        if self
+            .notice
+            .as_ref()
+            .is_some_and(|notice| notice.shown.elapsed() > NOTICE_DURATION)
+        {
+            self.notice = None;
+        }
+
+        if let Ok((url, result)) = self.check_receiver.try_recv() {
+            // The URL may have been edited while it was checked
+            if let AppState::AddFeed(dialog) = &mut self.app_state {
+                if dialog.url == url && dialog.check == Check::Checking {
+                    dialog.selected = 0;
+                    dialog.check = match result {
+                        Ok(candidates) => Check::Found(candidates),
+                        Err(e) => Check::Failed(e.to_string()),
+                    };
+                }
+            }
+        }
+    }
+
+    // Adds the stored articles after removing those outside of the retention.
+    // A new database is filled from the cached feed responses.
+    pub fn load_stored_articles(&mut self) {
+        let subscribed: Vec<&str> = self.subscriptions.iter().map(|s| s.url.as_str()).collect();
+        let _ = self.store.gc(&subscribed, self.config.retention);
+
+        if self.store.is_empty().unwrap_or(false) {
+            for subscription in &self.subscriptions {
+                let Some((_, body)) = self.feed_cache.load(&subscription.url) else {
+                    continue;
+                };
+                if let Ok(feed) = parse_feed(&subscription.url, &body) {
+                    if let Ok(upserted) = self.store.upsert(&feed.articles) {
+                        self.read_articles.extend(upserted.read);
+                    }
+                }
+            }
+        }
+
+        let custom_titles: FxHashMap<&str, &String> = self
+            .subscriptions
+            .iter()
+            .filter_map(|s| Some((s.url.as_str(), s.custom_title.as_ref()?)))
+            .collect();
+        let mut articles = self.store.load().unwrap_or_default();
+        for article in &mut articles {
+            if let Some(title) = custom_titles.get(article.feed_id.as_str()) {
+                article.feed_title.clone_from(title);
+            }
+        }
+        // Stored articles are unique, they only need to be sorted
+        articles.sort_by_key(|article| std::cmp::Reverse(article.date()));
+        self.articles = articles;
+        self.update_visible(None);
+    }
+
+    // Adds the fetched articles and plans the next fetch of each feed.
+    // The selected article stays selected while others get inserted around it.
+    fn merge_updates(&mut self, updates: Vec<FeedUpdate>) {
+        let selected = self.selected_article().cloned();
+
+        for update in updates {
+            self.feed_status
+                .entry(update.url.clone())
+                .or_default()
+                .update(&update.result);
+            match update.result {
+                Ok(feed) => {
+                    self.scheduler.fetched(&update.url, Some(feed.hints));
+                    let custom_title = self
+                        .subscriptions
+                        .iter()
+                        .find(|subscription| subscription.url == update.url)
+                        .and_then(|subscription| subscription.custom_title.clone());
+                    let mut articles = feed.articles;
+                    if let Ok(upserted) = self.store.upsert(&articles) {
+                        self.read_articles.extend(upserted.read);
+                        articles.retain(|article| !upserted.removed.contains(article));
+                    }
+                    if let Some(title) = &custom_title {
+                        for article in &mut articles {
+                            article.feed_title.clone_from(title);
+                        }
+                    }
+                    self.merge_articles(articles);
+                }
+                Err(_) => self.scheduler.fetched(&update.url, None),
+            }
+        }
+
+        self.update_visible(selected.as_ref());
+    }
+
+    // Recomputes the articles shown in the list.
+    // `selected` stays selected if it is still shown, otherwise the first article is.
+    fn update_visible(&mut self, selected: Option<&Article>) {
+        let starred_only = self.filter == Some(Filter::Starred);
+        let urls = match &self.filter {
+            Some(Filter::Starred) | None => None,
+            Some(filter) => Some(self.filter_urls(filter)),
+        };
+        self.visible = self
+            .articles
+            .iter()
+            .enumerate()
+            .filter(|(_, article)| {
+                urls.as_ref()
+                    .is_none_or(|urls| urls.contains(&article.feed_id))
+                    && (!starred_only || self.starred_articles.contains(article))
+                    && self
+                        .search
+                        .as_mut()
+                        .is_none_or(|search| search.matches(article))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let index = selected.and_then(|selected| {
+            self.visible
+                .iter()
+                .position(|&index| self.articles[index] == *selected)
+        });
+        match index {
+            Some(index) => self.list_state.select(Some(index)),
+            None if self.visible.is_empty() => self.list_state.select(None),
+            None => self.list_state.select(Some(0)),
+        }
+    }
+
+    // Adds fetched articles sorted by date, newest first.
+    // Already known articles are replaced by their new version instead.
+    fn merge_articles(&mut self, articles: Vec<Article>) {
+        if let AppState::Popup(displayed_article) = &mut self.app_state {
+            if let Some(article) = articles.iter().rev().find(|a| *a == &**displayed_article) {
+                **displayed_article = article.clone();
+                self.current_description = description_document(article, &self.config.styles);
+            }
+        }
+
+        // Positions of the fetched articles by feed and id, a later duplicate wins
+        let mut positions: FxHashMap<String, FxHashMap<String, usize>> = FxHashMap::default();
+        let mut fetched: Vec<Option<Article>> = Vec::with_capacity(articles.len());
+        for article in articles {
+            let ids = positions.entry(article.feed_id.clone()).or_default();
+            if let Some(index) = ids.insert(article.id.clone(), fetched.len()) {
+                fetched[index] = None;
+            }
+            fetched.push(Some(article));
+        }
+
+        // Known articles with the same date are replaced in place, others are sorted in again
+        self.articles.retain_mut(|known| {
+            let Some(&index) = positions
+                .get(&known.feed_id)
+                .and_then(|ids| ids.get(&known.id))
+            else {
+                return true;
+            };
+            match fetched[index].take_if(|article| article.date() == known.date()) {
+                Some(article) => {
+                    *known = article;
+                    true
+                }
+                None => false,
+            }
+        });
+
+        // The sort is stable, so new articles come after known ones of the same date
+        self.articles.extend(fetched.into_iter().flatten());
+        self.articles
+            .sort_by_key(|article| std::cmp::Reverse(article.date()));
     }
 
     // Close all open channels before shutting down
@@ -87,7 +416,7 @@ impl App {
     pub fn select_previous(&mut self) {
         if let Some(index) = self.list_state.selected() {
             self.list_state
-                .select(Some((index + self.feeds.len() - 1) % self.feeds.len()));
+                .select(Some((index + self.visible.len() - 1) % self.visible.len()));
             if let AppState::Popup(_) = &self.app_state {
                 self.update_displayed_feed();
             }
@@ -96,87 +425,819 @@ impl App {
 
     pub fn select_next(&mut self) {
         if let Some(index) = self.list_state.selected() {
-            self.list_state.select(Some((index + 1) % self.feeds.len()));
+            self.list_state
+                .select(Some((index + 1) % self.visible.len()));
             if let AppState::Popup(_) = &self.app_state {
                 self.update_displayed_feed();
             }
         }
     }
 
-    pub async fn add_feed(&mut self, url: &str) -> anyhow::Result<String> {
-        let title = check_url(url).await?;
+    // Selects the entry at the given position of the list.
+    pub fn select(&mut self, index: usize) {
+        if index < self.visible.len() {
+            self.list_state.select(Some(index));
+            if let AppState::Popup(_) = &self.app_state {
+                self.update_displayed_feed();
+            }
+        }
+    }
+
+    pub fn selected_article(&self) -> Option<&Article> {
+        self.list_state
+            .selected()
+            .and_then(|selected| self.visible.get(selected))
+            .and_then(|&index| self.articles.get(index))
+    }
+
+    pub fn toggle_sidebar(&mut self) {
+        self.sidebar_visible = !self.sidebar_visible;
+        if !self.sidebar_visible {
+            self.focus = Focus::List;
+        }
+    }
+
+    pub fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::List if self.sidebar_visible => Focus::Sidebar,
+            _ => Focus::List,
+        };
+    }
+
+    pub fn select_previous_sidebar_entry(&mut self) {
+        let count = self.sidebar.len();
+        let index = self.sidebar_state.selected().unwrap_or(0);
+        self.select_sidebar_entry((index + count - 1) % count);
+    }
+
+    pub fn select_next_sidebar_entry(&mut self) {
+        let count = self.sidebar.len();
+        let index = self.sidebar_state.selected().unwrap_or(0);
+        self.select_sidebar_entry((index + 1) % count);
+    }
+
+    // Selects an entry of the sidebar and shows only the articles of that feed or folder.
+    pub fn select_sidebar_entry(&mut self, index: usize) {
+        self.sidebar_state.select(Some(index));
+        self.filter = self
+            .sidebar
+            .get(index)
+            .and_then(|entry| entry.filter.clone());
+        self.update_visible(None);
+    }
+
+    // Name shown for a subscription, a title given by the user before the fetched one.
+    pub fn feed_name<'a>(&'a self, subscription: &'a Subscription) -> &'a str {
+        let fetched = self
+            .feed_status
+            .get(&subscription.url)
+            .and_then(|status| status.title.as_deref());
+        match (&subscription.custom_title, fetched) {
+            (Some(custom_title), _) => custom_title,
+            (None, Some(title)) => title,
+            (None, None) => subscription.display_name(),
+        }
+    }
+
+    // URLs of the subscriptions the filter includes.
+    pub fn filter_urls(&self, filter: &Filter) -> FxHashSet<String> {
+        self.subscriptions
+            .iter()
+            .filter(|subscription| filter.includes(subscription))
+            .map(|subscription| subscription.url.clone())
+            .collect()
+    }
+
+    // Opens the search prompt, editing the current query if a search is active.
+    pub fn start_search(&mut self) {
+        match &mut self.search {
+            Some(search) => search.editing = true,
+            None => self.search = Some(Search::new(self.selected_article().cloned())),
+        }
+    }
+
+    pub fn push_search_char(&mut self, c: char) {
+        if let Some(search) = &mut self.search {
+            search.query.push(c);
+            self.update_search();
+        }
+    }
+
+    pub fn pop_search_char(&mut self) {
+        if let Some(search) = &mut self.search {
+            search.query.pop();
+            self.update_search();
+        }
+    }
+
+    fn update_search(&mut self) {
+        let selected = self.selected_article().cloned();
+        self.update_visible(selected.as_ref());
+    }
+
+    // Closes the prompt but keeps the list filtered, an empty search is cancelled.
+    pub fn confirm_search(&mut self) {
+        match &mut self.search {
+            Some(search) if search.query.is_empty() => self.cancel_search(),
+            Some(search) => search.editing = false,
+            None => {}
+        }
+    }
+
+    // Shows the whole list again with the selection from before the search.
+    pub fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            let selected = search
+                .previous_selection
+                .or_else(|| self.selected_article().cloned());
+            self.update_visible(selected.as_ref());
+        }
+    }
+
+    // Number of unread articles per feed.
+    pub fn unread_counts(&self) -> FxHashMap<&str, usize> {
+        let mut counts = FxHashMap::default();
+        for article in self
+            .articles
+            .iter()
+            .filter(|article| !self.is_read(article))
+        {
+            *counts.entry(article.feed_id.as_str()).or_default() += 1;
+        }
+        counts
+    }
+
+    // Fetches the feed of the selected article, or the feeds selected in the sidebar, again.
+    pub fn refresh_selected_feed(&mut self) {
+        match (self.focus, &self.filter) {
+            (Focus::Sidebar, None | Some(Filter::Starred)) => self.scheduler.request_all(),
+            (Focus::Sidebar, Some(filter)) => {
+                for url in self.filter_urls(filter) {
+                    self.scheduler.request(&url);
+                }
+            }
+            (Focus::List, _) => {
+                if let Some(url) = self
+                    .selected_article()
+                    .map(|article| article.feed_id.clone())
+                {
+                    self.scheduler.request(&url);
+                }
+            }
+        }
+    }
+
+    pub fn refresh_all(&mut self) {
+        self.scheduler.request_all();
+    }
+
+    // Number of feeds whose last fetch failed.
+    pub fn failing_feeds(&self) -> usize {
+        self.feed_status
+            .values()
+            .filter(|status| status.error.is_some())
+            .count()
+    }
+
+    pub fn open_feeds_view(&mut self) {
+        if self.feeds_table_state.selected().is_none() && !self.subscriptions.is_empty() {
+            self.feeds_table_state.select(Some(0));
+        }
+        self.app_state = AppState::Feeds;
+    }
+
+    pub fn select_previous_subscription(&mut self) {
+        if let Some(index) = self.feeds_table_state.selected() {
+            let count = self.subscriptions.len();
+            self.feeds_table_state
+                .select(Some((index + count - 1) % count));
+        }
+    }
+
+    pub fn select_next_subscription(&mut self) {
+        if let Some(index) = self.feeds_table_state.selected() {
+            let count = self.subscriptions.len();
+            self.feeds_table_state.select(Some((index + 1) % count));
+        }
+    }
+
+    // Fetches the feed highlighted in the feeds view again.
+    pub fn refresh_selected_subscription(&mut self) {
+        if let Some(subscription) = self
+            .feeds_table_state
+            .selected()
+            .and_then(|selected| self.subscriptions.get(selected))
+        {
+            self.scheduler.request(&subscription.url);
+        }
+    }
+
+    // Opens the popup for the selected article and marks it as read.
+    pub fn open_popup(&mut self) {
+        self.update_displayed_feed();
+    }
+
+    // Shows the selected article in the reader and marks it as read.
+    pub fn open_reader(&mut self) {
+        let from_popup = matches!(self.app_state, AppState::Popup(_));
+        let Some(article) = self.selected_article().cloned() else {
+            return;
+        };
+        self.mark_read(&article);
+        let reader = Reader::new(article, from_popup, &self.config.styles);
+        self.app_state = AppState::Reader(Box::new(reader));
+    }
+
+    pub fn close_reader(&mut self) {
+        match &self.app_state {
+            AppState::Reader(reader) if reader.from_popup => self.update_displayed_feed(),
+            _ => self.app_state = AppState::List,
+        }
+    }
 
-        self.feed_urls.push(url.to_string());
-        if let Err(a) = self.save() {
-            return Err(anyhow::Error::msg(a.to_string()));
+    pub fn notify(&mut self, text: String, error: bool) {
+        self.notice = Some(Notice {
+            text,
+            error,
+            shown: Instant::now(),
+        });
+    }
+
+    // Article shown in the popup or reader, otherwise the one selected in the list.
+    fn current_article(&self) -> Option<&Article> {
+        match &self.app_state {
+            AppState::Popup(article) => Some(article),
+            AppState::Reader(reader) => Some(&reader.article),
+            _ => self.selected_article(),
         }
+    }
+
+    pub fn download_dir(&self) -> PathBuf {
+        self.config
+            .download_dir
+            .clone()
+            .unwrap_or_else(|| self.paths.download_dir())
+    }
 
-        Ok(title)
+    // File the enclosure of an article from the feed is downloaded to.
+    pub fn enclosure_path(&self, feed_title: &str, enclosure: &Enclosure) -> PathBuf {
+        download::target_path(&self.download_dir(), feed_title, enclosure)
     }
 
-    fn load() -> Vec<String> {
-        let exe_path = env::current_exe().unwrap();
-        let reading_file_path = exe_path.parent().unwrap().join("feeds.json");
-        match std::fs::read_to_string(reading_file_path) {
-            Ok(valid_content) => match serde_json::from_str(&valid_content) {
-                Ok(feed_urls) => feed_urls,
-                Err(_) => Vec::new(),
+    // Hands the enclosure of the current article to the player, a downloaded file is played instead of the URL.
+    pub fn play_enclosure(&mut self) {
+        let Some(article) = self.current_article() else {
+            return;
+        };
+        let Some(enclosure) = article.media_enclosure() else {
+            self.notify("The article has no enclosure".to_string(), true);
+            return;
+        };
+        let path = self.enclosure_path(&article.feed_title, enclosure);
+        let target = if path.is_file() {
+            path.into_os_string()
+        } else {
+            enclosure.url.clone().into()
+        };
+
+        let result = match self.config.player.as_deref().map(str::split_whitespace) {
+            Some(mut command) => match command.next() {
+                Some(program) => Command::new(program)
+                    .args(command)
+                    .arg(&target)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .map(|_| ()),
+                None => Ok(()),
             },
+            None => {
+                open::that_in_background(&target);
+                Ok(())
+            }
+        };
+        match result {
+            Ok(()) => self.notify(format!("Playing {}", enclosure.file_name()), false),
+            Err(e) => self.notify(format!("Failed to start the player: {}", e), true),
+        }
+    }
+
+    // Saves the enclosure of the current article in the download directory.
+    pub fn download_enclosure(&mut self) {
+        let Some(article) = self.current_article() else {
+            return;
+        };
+        let Some(enclosure) = article.media_enclosure() else {
+            self.notify("The article has no enclosure".to_string(), true);
+            return;
+        };
+        let path = self.enclosure_path(&article.feed_title, enclosure);
+        let enclosure = enclosure.clone();
+        if path.is_file() {
+            self.notify(format!("Already downloaded to {}", path.display()), false);
+        } else if !self.downloads.start(&enclosure, path) {
+            self.notify("Already downloading".to_string(), false);
+        }
+    }
+
+    pub fn is_read(&self, article: &Article) -> bool {
+        self.read_articles.contains(article)
+    }
+
+    fn mark_read(&mut self, article: &Article) {
+        if self.read_articles.insert(article) {
+            let _ = self.store.set_read(article, true);
+        }
+    }
+
+    // Flips the read state of the selected article.
+    pub fn toggle_read(&mut self) {
+        if let Some(article) = self.selected_article().cloned() {
+            let read = !self.read_articles.remove(&article);
+            if read {
+                self.read_articles.insert(&article);
+            }
+            let _ = self.store.set_read(&article, read);
+        }
+    }
+
+    pub fn is_starred(&self, article: &Article) -> bool {
+        self.starred_articles.contains(article)
+    }
+
+    // Flips the starred state of the current article, starred articles are kept in the
+    // database after they disappeared from their feed.
+    pub fn toggle_star(&mut self) {
+        if let Some(article) = self.current_article().cloned() {
+            let starred = !self.starred_articles.remove(&article);
+            if starred {
+                self.starred_articles.insert(&article);
+            }
+            let _ = self.store.set_starred(&article, starred);
+        }
+    }
+
+    // Subscribes to the feed at the URL or one announced by the web page at the URL,
+    // asking which one if the page lists several.
+    pub async fn add_feed(&mut self, url: &str) -> anyhow::Result<String> {
+        let candidates = discover(self.fetcher.client(), url).await?;
+        let candidate = match candidates.as_slice() {
+            [candidate] => candidate,
+            candidates => pick_candidate(candidates)?,
+        };
+        self.subscribe(&candidate.url, &candidate.title)?;
+        Ok(candidate.title.clone())
+    }
+
+    // Adds a feed whose URL was already checked and returns to the list.
+    fn subscribe(&mut self, url: &str, title: &str) -> anyhow::Result<()> {
+        if self
+            .subscriptions
+            .iter()
+            .any(|subscription| subscription.url == url)
+        {
+            anyhow::bail!("Already subscribed to {}", url);
+        }
+
+        let subscription = Subscription {
+            title: Some(title.to_string()),
+            ..Subscription::new(url)
+        };
+        self.scheduler.add(&subscription);
+        self.feed_status.insert(
+            url.to_string(),
+            FeedStatus {
+                title: Some(title.to_string()),
+                ..Default::default()
+            },
+        );
+        self.subscriptions.push(subscription);
+        self.sidebar = sidebar::entries(&self.subscriptions);
+        self.save()?;
+        Ok(())
+    }
+
+    // Opens the add feed dialog, a given URL is checked right away.
+    pub fn open_add_feed(&mut self, url: &str) {
+        self.app_state = AppState::AddFeed(Box::new(AddFeed::new(url)));
+        if !url.is_empty() {
+            self.check_add_feed_url();
+        }
+    }
+
+    // Fetches the URL of the dialog in the background, `tick` receives the result.
+    fn check_add_feed_url(&mut self) {
+        let AppState::AddFeed(dialog) = &mut self.app_state else {
+            return;
+        };
+        let url = dialog.url.trim().to_string();
+        dialog.url.clone_from(&url);
+        dialog.check = Check::Checking;
+
+        let (tx, client) = (self.check_sender.clone(), self.fetcher.client().clone());
+        tokio::spawn(async move {
+            let result = discover(&client, &url).await;
+            tx.send((url, result)).await.unwrap_or_default();
+        });
+    }
+
+    // Checks the URL of the dialog, or subscribes to it once it was found to be a feed.
+    pub fn confirm_add_feed(&mut self) {
+        let AppState::AddFeed(dialog) = &mut self.app_state else {
+            return;
+        };
+        match &dialog.check {
+            Check::Found(_) => {
+                let Some(Candidate { url, title }) = dialog.selected_candidate().cloned() else {
+                    return;
+                };
+                match self.subscribe(&url, &title) {
+                    Ok(()) => self.app_state = AppState::List,
+                    Err(e) => {
+                        if let AppState::AddFeed(dialog) = &mut self.app_state {
+                            dialog.check = Check::Failed(e.to_string());
+                        }
+                    }
+                }
+            }
+            Check::Checking => {}
+            Check::Unchecked | Check::Failed(_) if dialog.url.trim().is_empty() => {}
+            Check::Unchecked | Check::Failed(_) => self.check_add_feed_url(),
+        }
+    }
+
+    fn load(paths: &Paths) -> Vec<Subscription> {
+        match std::fs::read_to_string(paths.feeds_file()) {
+            Ok(valid_content) => serde_json::from_str(&valid_content).unwrap_or_default(),
             Err(_) => Vec::new(),
         }
     }
 
     fn save(&self) -> std::io::Result<()> {
-        let exe_path = env::current_exe().unwrap();
-        let output_file_path = exe_path.parent().unwrap().join("feeds.json");
-        let content = serde_json::to_string(&self.feed_urls).unwrap();
-        fs::write(output_file_path, content)
+        let content = serde_json::to_string_pretty(&self.subscriptions)?;
+        write_file(&self.paths.feeds_file(), content)
     }
 
     fn update_displayed_feed(&mut self) {
         self.current_feed_image = None;
-        if let Some(selected) = self.list_state.selected() {
-            let displayed_feed = self.feeds.get(selected).unwrap();
-            self.app_state = AppState::Popup(displayed_feed.clone());
-
-            if let Some(feed_image_url) = displayed_feed.get_image_url() {
-                if self.cached_images.contains_key(&feed_image_url) {
-                    self.current_feed_image =
-                        Some(self.cached_images.get(&feed_image_url).unwrap().clone());
-                } else {
-                    let tx = self.image_sender.clone();
-                    tokio::spawn(async move {
-                        let image_bytes = reqwest::get(&feed_image_url)
-                            .await
-                            .unwrap()
-                            .bytes()
-                            .await
-                            .unwrap();
-
-                        let b = image::load_from_memory(&image_bytes).unwrap();
+        let Some(displayed_article) = self.selected_article().cloned() else {
+            return;
+        };
+        self.mark_read(&displayed_article);
+        let image_url = displayed_article.image_url().map(str::to_string);
+        self.current_description = description_document(&displayed_article, &self.config.styles);
+        self.app_state = AppState::Popup(Box::new(displayed_article));
+
+        if let Some(feed_image_url) = image_url {
+            if self.cached_images.contains_key(&feed_image_url) {
+                self.current_feed_image =
+                    Some(self.cached_images.get(&feed_image_url).unwrap().clone());
+            } else {
+                let (tx, client) = (self.image_sender.clone(), self.fetcher.client().clone());
+                tokio::spawn(async move {
+                    // Images that can't be fetched or decoded are left out of the popup
+                    let image = async {
+                        let response = client.get(&feed_image_url).send().await.ok()?;
+                        let bytes = response.error_for_status().ok()?.bytes().await.ok()?;
+                        let image = image::load_from_memory(&bytes).ok()?;
                         let mut picker = Picker::new((8, 15));
                         picker.protocol_type = picker.guess_protocol();
+                        Some(picker.new_resize_protocol(image))
+                    };
+                    let Some(image) = image.await else {
+                        return;
+                    };
+                    let _result = tx.send((feed_image_url, image)).await;
+                });
+            }
+        }
+    }
+
+    // Removes old articles from the database, the limits default to the configured retention.
+    pub fn gc(&self, days: Option<u64>, per_feed: Option<usize>) -> anyhow::Result<usize> {
+        let retention = Retention {
+            days: days.or(self.config.retention.days),
+            per_feed: per_feed.or(self.config.retention.per_feed),
+        };
+        let subscribed: Vec<&str> = self.subscriptions.iter().map(|s| s.url.as_str()).collect();
+        let removed = self.store.gc(&subscribed, retention)?;
+        self.store.vacuum()?;
+        Ok(removed)
+    }
 
-                        let image = picker.new_resize_protocol(b);
-                        let _result = tx.send((feed_image_url, image)).await;
-                    });
+    // Fetches every feed once into the database, returns the feeds that failed with their error.
+    pub async fn fetch_feeds(&self) -> anyhow::Result<Vec<(&Subscription, FetchError)>> {
+        let results = join_all(self.subscriptions.iter().map(|subscription| {
+            self.fetcher
+                .fetch(&subscription.url, Some(&self.feed_cache))
+        }))
+        .await;
+
+        let mut failed = Vec::new();
+        for (subscription, result) in self.subscriptions.iter().zip(results) {
+            match result {
+                Ok(feed) => {
+                    self.store.upsert(&feed.articles)?;
+                }
+                Err(e) => failed.push((subscription, e)),
+            }
+        }
+        let subscribed: Vec<&str> = self.subscriptions.iter().map(|s| s.url.as_str()).collect();
+        self.store.gc(&subscribed, self.config.retention)?;
+        Ok(failed)
+    }
+
+    // Stored articles that match the query, the newest first.
+    pub async fn query_articles(&self, query: &Query) -> anyhow::Result<Vec<Article>> {
+        let feed = match &query.feed {
+            Some(feed) => Some(
+                self.subscriptions[self.resolve_feed(feed).await?.0]
+                    .url
+                    .clone(),
+            ),
+            None => None,
+        };
+        let folder_urls = match &query.folder {
+            Some(folder) => {
+                let folder = normalize_folder(folder)
+                    .ok_or_else(|| anyhow::anyhow!("The folder name is empty"))?;
+                Some(self.filter_urls(&Filter::Folder(folder)))
+            }
+            None => None,
+        };
+        let mut search = query.search.as_ref().map(|text| {
+            let mut search = Search::default();
+            search.query.clone_from(text);
+            search
+        });
+
+        let mut articles = Vec::new();
+        for mut article in self.store.load()? {
+            let subscription = self
+                .subscriptions
+                .iter()
+                .find(|subscription| subscription.url == article.feed_id);
+            if let Some(title) = subscription.and_then(|s| s.custom_title.as_ref()) {
+                article.feed_title.clone_from(title);
+            }
+            let matches = feed.as_ref().is_none_or(|url| article.feed_id == *url)
+                && folder_urls
+                    .as_ref()
+                    .is_none_or(|urls| urls.contains(&article.feed_id))
+                && query.in_range(&article)
+                && !(query.unread && self.is_read(&article))
+                && (!query.starred || self.is_starred(&article))
+                && search
+                    .as_mut()
+                    .is_none_or(|search| search.matches(&article));
+            if matches {
+                articles.push(article);
+            }
+        }
+        // Articles without a date come last, as in the list
+        articles.sort_by_key(|article| std::cmp::Reverse(article.date()));
+        if let Some(limit) = query.limit {
+            articles.truncate(limit);
+        }
+        Ok(articles)
+    }
+
+    // Fetches every feed once and prints its status, returns the number of failed feeds.
+    pub async fn check_feeds(&self) -> usize {
+        let results = join_all(
+            self.subscriptions
+                .iter()
+                .map(|subscription| self.fetcher.fetch(&subscription.url, None)),
+        )
+        .await;
+
+        let mut failed = 0;
+        for (subscription, result) in self.subscriptions.iter().zip(results) {
+            match result {
+                Ok(feed) => println!(
+                    "OK    {} ({} articles)\n      {}",
+                    feed.title,
+                    feed.articles.len(),
+                    subscription.url
+                ),
+                Err(e) => {
+                    failed += 1;
+                    let status = FeedStatus {
+                        last_success: self.feed_cache.last_success(&subscription.url),
+                        ..Default::default()
+                    };
+                    println!(
+                        "ERROR {}: {}\n      {}\n      Last success: {}",
+                        subscription.display_name(),
+                        e,
+                        subscription.url,
+                        status.last_success_string()
+                    );
                 }
             }
         }
+        failed
     }
 
     pub fn print_feeds(&self) {
-        if self.feed_urls.is_empty() {
+        if self.subscriptions.is_empty() {
             println!("No feeds found.");
         } else {
-            for url in &self.feed_urls {
-                println!("{}", url);
+            for (index, subscription) in self.subscriptions.iter().enumerate() {
+                let mut line = format!("{:>3}: {}", index + 1, subscription.url);
+                if subscription.display_name() != subscription.url {
+                    line = format!("{} ({})", line, subscription.display_name());
+                }
+                if !subscription.folders.is_empty() {
+                    line = format!("{} [{}]", line, subscription.folders.join(", "));
+                }
+                println!("{}", line);
             }
         }
     }
 
-    pub fn remove_feed(&mut self, _url: &str) -> anyhow::Result<String> {
-        todo!()
+    // Puts a feed, given like for `remove_feed`, into a folder and returns its title and the folder.
+    pub async fn tag_feed(
+        &mut self,
+        query: &str,
+        folder: &str,
+    ) -> anyhow::Result<(String, String)> {
+        let folder =
+            normalize_folder(folder).ok_or_else(|| anyhow::anyhow!("The folder name is empty"))?;
+        let (index, title) = self.resolve_feed(query).await?;
+        let subscription = &mut self.subscriptions[index];
+        if subscription.folders.contains(&folder) {
+            anyhow::bail!("The feed is already in {}", folder);
+        }
+        subscription.folders.push(folder.clone());
+        let title = title.unwrap_or_else(|| subscription.display_name().to_string());
+        self.save()?;
+        Ok((title, folder))
+    }
+
+    // Takes a feed out of a folder and returns its title and the folder.
+    pub async fn untag_feed(
+        &mut self,
+        query: &str,
+        folder: &str,
+    ) -> anyhow::Result<(String, String)> {
+        let folder = normalize_folder(folder).unwrap_or_default();
+        let (index, title) = self.resolve_feed(query).await?;
+        let subscription = &mut self.subscriptions[index];
+        let count = subscription.folders.len();
+        subscription.folders.retain(|path| *path != folder);
+        if subscription.folders.len() == count {
+            anyhow::bail!("The feed is not in {}", folder);
+        }
+        let title = title.unwrap_or_else(|| subscription.display_name().to_string());
+        self.save()?;
+        Ok((title, folder))
+    }
+
+    // Sets the title shown for a feed, `None` shows the title of the feed again.
+    pub async fn rename_feed(&mut self, query: &str, title: Option<&str>) -> anyhow::Result<()> {
+        let (index, _) = self.resolve_feed(query).await?;
+        self.subscriptions[index].custom_title = title
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .map(str::to_string);
+        self.save()?;
+        Ok(())
+    }
+
+    // Removes a feed given its exact URL, its index from `ta-rss list` or its title
+    // and returns the title of the removed feed.
+    pub async fn remove_feed(&mut self, query: &str) -> anyhow::Result<String> {
+        let (index, title) = self.resolve_feed(query).await?;
+        let subscription = self.subscriptions.remove(index);
+        self.save()?;
+
+        match title.or(subscription.title) {
+            Some(title) => Ok(title),
+            None => Ok(check_url(&self.fetcher, &subscription.url)
+                .await
+                .unwrap_or(subscription.url)),
+        }
+    }
+
+    // Finds the position of the feed the query refers to.
+    // The title is only returned if it had to be fetched to resolve the query.
+    async fn resolve_feed(&self, query: &str) -> anyhow::Result<(usize, Option<String>)> {
+        if let Some(index) = self
+            .subscriptions
+            .iter()
+            .position(|subscription| subscription.url == query)
+        {
+            return Ok((index, None));
+        }
+
+        if let Ok(number) = query.parse::<usize>() {
+            if (1..=self.subscriptions.len()).contains(&number) {
+                return Ok((number - 1, None));
+            }
+            anyhow::bail!("No feed with index {}, see `ta-rss list`", number);
+        }
+
+        // Feeds added by older versions have no stored title and have to be fetched
+        let titles = join_all(self.subscriptions.iter().map(|subscription| async {
+            match (&subscription.custom_title, &subscription.title) {
+                (Some(custom_title), _) if custom_title.eq_ignore_ascii_case(query.trim()) => {
+                    Ok(custom_title.clone())
+                }
+                (_, Some(title)) => Ok(title.clone()),
+                (_, None) => check_url(&self.fetcher, &subscription.url).await,
+            }
+        }))
+        .await;
+        let mut matches = titles
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, title)| match title {
+                Ok(title) if title.eq_ignore_ascii_case(query.trim()) => Some((index, title)),
+                _ => None,
+            });
+
+        match (matches.next(), matches.next()) {
+            (Some((index, title)), None) => Ok((index, Some(title))),
+            (Some(_), Some(_)) => anyhow::bail!(
+                "Several feeds are titled '{}', remove it by URL or index instead",
+                query
+            ),
+            (None, _) => anyhow::bail!("No feed matches '{}'", query),
+        }
+    }
+
+    // Adds all feeds of an OPML file that are not subscribed yet and returns how many were added.
+    // With `verify` every feed is fetched first and skipped if it can't be parsed.
+    pub async fn import_opml(&mut self, file: &Path, verify: bool) -> anyhow::Result<usize> {
+        let content = fs::read_to_string(file)?;
+        let mut imported = opml::parse(&content)?;
+        // Known feeds only take over the folders of the file
+        imported.retain_mut(|new| {
+            let Some(known) = self
+                .subscriptions
+                .iter_mut()
+                .find(|subscription| subscription.url == new.url)
+            else {
+                return true;
+            };
+            if opml::merge_folders(known, std::mem::take(&mut new.folders)) {
+                eprintln!("Added the folders of {}", new.url);
+            } else {
+                eprintln!("Skipped {}: already subscribed", new.url);
+            }
+            false
+        });
+        if verify {
+            let titles = join_all(
+                imported
+                    .iter()
+                    .map(|new| check_url(&self.fetcher, &new.url)),
+            )
+            .await;
+            let mut verified = Vec::with_capacity(imported.len());
+            for (mut new, title) in imported.into_iter().zip(titles) {
+                match title {
+                    Ok(title) => {
+                        new.title.get_or_insert(title);
+                        verified.push(new);
+                    }
+                    Err(e) => eprintln!("Skipped {}: {}", new.url, e),
+                }
+            }
+            imported = verified;
+        }
+
+        let count = imported.len();
+        self.subscriptions.extend(imported);
+        self.save()?;
+        Ok(count)
+    }
+
+    // Starred articles in the format, newest first.
+    pub fn export_starred(&self, format: export::Format) -> anyhow::Result<String> {
+        let mut articles = self.store.load_starred()?;
+        for article in &mut articles {
+            let subscription = self
+                .subscriptions
+                .iter()
+                .find(|subscription| subscription.url == article.feed_id);
+            if let Some(title) = subscription.and_then(|s| s.custom_title.as_ref()) {
+                article.feed_title.clone_from(title);
+            }
+        }
+        export::write(&articles, format)
+    }
+
+    // Writes all subscriptions as OPML to the given file or to stdout.
+    pub fn export_opml(&self, file: Option<&Path>) -> anyhow::Result<()> {
+        let content = opml::write(&self.subscriptions)?;
+        match file {
+            Some(file) => write_file(file, content)?,
+            None => println!("{}", content),
+        }
+        Ok(())
     }
 }
 
@@ -187,3 +1248,38 @@ pub struct Popup<'a> {
     pub image: Option<Box<dyn StatefulProtocol>>,
     pub source: Option<&'a str>,
 }
+
+fn description_document(article: &Article, theme: &Theme) -> Document {
+    Document::from_html(article.description(), article.link.as_deref(), theme)
+}
+
+// Asks on the terminal which of several discovered feeds to subscribe to.
+fn pick_candidate(candidates: &[Candidate]) -> anyhow::Result<&Candidate> {
+    let list = candidates
+        .iter()
+        .enumerate()
+        .map(|(index, candidate)| {
+            format!("{:>3}: {} ({})", index + 1, candidate.url, candidate.title)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("Found several feeds, add one of them by its URL:\n{}", list);
+    }
+
+    println!("Found several feeds:\n{}", list);
+    loop {
+        print!("Subscribe to [1-{}]: ", candidates.len());
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            anyhow::bail!("No feed selected");
+        }
+        match answer.trim().parse::<usize>() {
+            Ok(number) if (1..=candidates.len()).contains(&number) => {
+                return Ok(&candidates[number - 1])
+            }
+            _ => println!("Enter a number between 1 and {}", candidates.len()),
+        }
+    }
+}
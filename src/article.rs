@@ -0,0 +1,473 @@
+use crate::hash::fnv1a;
+use crate::json_feed;
+use atom_syndication::TextType;
+use chrono::{DateTime, Datelike, FixedOffset};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// An entry of any supported feed format, normalized when the feed is parsed.
+#[derive(Clone, Debug, Default)]
+pub struct Article {
+    // Stable identifier, the RSS guid, Atom id or JSON Feed id.
+    // Falls back to a hash of link and title for entries without one.
+    pub id: String,
+    // URL of the subscription the article was fetched from.
+    pub feed_id: String,
+    pub feed_title: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub authors: Vec<String>,
+    pub categories: Vec<String>,
+    // HTML of the summary and of the full content, plain text feeds are escaped into HTML.
+    pub summary: Option<String>,
+    pub content: Option<String>,
+    pub enclosures: Vec<Enclosure>,
+    pub images: Vec<String>,
+    pub published: Option<DateTime<FixedOffset>>,
+    pub updated: Option<DateTime<FixedOffset>>,
+}
+
+// Articles identified by feed and id, ids are only unique within their feed.
+#[derive(Clone, Debug, Default)]
+pub struct ArticleSet {
+    ids: FxHashMap<String, FxHashSet<String>>,
+}
+
+impl ArticleSet {
+    pub fn contains(&self, article: &Article) -> bool {
+        self.ids
+            .get(&article.feed_id)
+            .is_some_and(|ids| ids.contains(&article.id))
+    }
+
+    // Returns whether the article wasn't in the set yet.
+    pub fn insert(&mut self, article: &Article) -> bool {
+        if self.contains(article) {
+            return false;
+        }
+        self.ids
+            .entry(article.feed_id.clone())
+            .or_default()
+            .insert(article.id.clone())
+    }
+
+    // Returns whether the article was in the set.
+    pub fn remove(&mut self, article: &Article) -> bool {
+        self.ids
+            .get_mut(&article.feed_id)
+            .is_some_and(|ids| ids.remove(&article.id))
+    }
+}
+
+// Pairs of feed and id.
+impl Extend<(String, String)> for ArticleSet {
+    fn extend<T: IntoIterator<Item = (String, String)>>(&mut self, keys: T) {
+        for (feed_id, id) in keys {
+            self.ids.entry(feed_id).or_default().insert(id);
+        }
+    }
+}
+
+impl IntoIterator for ArticleSet {
+    type Item = (String, String);
+    type IntoIter = Box<dyn Iterator<Item = (String, String)>>;
+
+    // Feed and id of every article.
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(
+            self.ids
+                .into_iter()
+                .flat_map(|(feed_id, ids)| ids.into_iter().map(move |id| (feed_id.clone(), id))),
+        )
+    }
+}
+
+impl FromIterator<(String, String)> for ArticleSet {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(keys: T) -> Self {
+        let mut set = Self::default();
+        set.extend(keys);
+        set
+    }
+}
+
+// A file attached to an article, e.g. a podcast episode.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Enclosure {
+    pub url: String,
+    pub mime_type: Option<String>,
+    pub length: Option<u64>,
+    pub duration: Option<String>,
+}
+
+impl Enclosure {
+    // Images are shown in the popup, everything else is played or downloaded.
+    pub fn is_media(&self) -> bool {
+        !self
+            .mime_type
+            .as_deref()
+            .is_some_and(|mime_type| mime_type.starts_with("image/"))
+    }
+
+    // Last segment of the URL path, e.g. `episode-12.mp3`.
+    pub fn file_name(&self) -> String {
+        let name = reqwest::Url::parse(&self.url)
+            .ok()
+            .and_then(|url| {
+                url.path_segments()
+                    .and_then(|mut segments| segments.next_back().map(str::to_string))
+            })
+            .unwrap_or_default();
+        match percent_decode(&name) {
+            name if name.trim().is_empty() => "download".to_string(),
+            name => name,
+        }
+    }
+}
+
+impl Article {
+    pub fn from_rss(item: &rss::Item, feed_id: &str, feed_title: &str) -> Self {
+        let dublin_core = item.dublin_core_ext();
+        let itunes = item.itunes_ext();
+
+        let link = item.link().map(str::to_string).or_else(|| {
+            item.guid()
+                .filter(|guid| guid.is_permalink())
+                .map(|guid| guid.value().to_string())
+        });
+        let id = match item.guid() {
+            Some(guid) if !guid.value().is_empty() => guid.value().to_string(),
+            _ => fallback_id(link.as_deref(), item.title()),
+        };
+
+        let mut authors: Vec<String> = item.author().map(str::to_string).into_iter().collect();
+        if let Some(dublin_core) = dublin_core {
+            authors.extend(dublin_core.creators().iter().cloned());
+        }
+
+        let enclosures = item
+            .enclosure()
+            .map(|enclosure| Enclosure {
+                url: enclosure.url().to_string(),
+                mime_type: non_empty(Some(enclosure.mime_type())),
+                length: enclosure.length().parse().ok(),
+                // iTunes durations are either `HH:MM:SS` or a number of seconds
+                duration: itunes.and_then(|itunes| itunes.duration()).map(
+                    |duration| match duration.trim().parse::<u64>() {
+                        Ok(seconds) => format_duration(seconds),
+                        Err(_) => duration.trim().to_string(),
+                    },
+                ),
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let mut images = media_images(item.extensions(), rss::extension::Extension::attrs);
+        images.extend(image_enclosures(&enclosures));
+        images.extend(itunes.and_then(|itunes| itunes.image()).map(str::to_string));
+
+        let published = item
+            .pub_date()
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .or_else(|| {
+                dublin_core
+                    .and_then(|dublin_core| dublin_core.dates().first())
+                    .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+            });
+
+        Self {
+            id,
+            feed_id: feed_id.to_string(),
+            feed_title: feed_title.to_string(),
+            title: title_or_fallback(item.title(), link.as_deref()),
+            link,
+            authors,
+            categories: item
+                .categories()
+                .iter()
+                .map(|category| category.name().to_string())
+                .collect(),
+            summary: non_empty(item.description()),
+            content: non_empty(item.content()),
+            enclosures,
+            images,
+            published,
+            updated: None,
+        }
+    }
+
+    pub fn from_atom(entry: &atom_syndication::Entry, feed_id: &str, feed_title: &str) -> Self {
+        let link = entry
+            .links()
+            .iter()
+            .find(|link| link.rel() == "alternate")
+            .or(entry.links().first())
+            .map(|link| link.href().to_string())
+            .or_else(|| Some(entry.id().to_string()).filter(|id| id.starts_with("http")));
+        let id = match entry.id().trim() {
+            "" => fallback_id(link.as_deref(), Some(entry.title().as_str())),
+            id => id.to_string(),
+        };
+
+        let enclosures = entry
+            .links()
+            .iter()
+            .filter(|link| link.rel() == "enclosure")
+            .map(|link| Enclosure {
+                url: link.href().to_string(),
+                mime_type: non_empty(link.mime_type()),
+                length: link.length().and_then(|length| length.parse().ok()),
+                duration: None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut images = media_images(
+            entry.extensions(),
+            atom_syndication::extension::Extension::attrs,
+        );
+        images.extend(image_enclosures(&enclosures));
+
+        Self {
+            id,
+            feed_id: feed_id.to_string(),
+            feed_title: feed_title.to_string(),
+            title: title_or_fallback(Some(entry.title().as_str()), link.as_deref()),
+            link,
+            authors: entry
+                .authors()
+                .iter()
+                .map(|person| person.name().to_string())
+                .collect(),
+            categories: entry
+                .categories()
+                .iter()
+                .map(|category| category.label().unwrap_or(category.term()).to_string())
+                .collect(),
+            summary: entry.summary().and_then(|summary| match summary.r#type {
+                TextType::Text => non_empty(Some(&text_to_html(&summary.value))),
+                TextType::Html | TextType::Xhtml => non_empty(Some(&summary.value)),
+            }),
+            content: entry.content().and_then(|content| {
+                let value = content.value()?;
+                match content.content_type() {
+                    Some("text") | Some("text/plain") => non_empty(Some(&text_to_html(value))),
+                    _ => non_empty(Some(value)),
+                }
+            }),
+            enclosures,
+            images,
+            published: entry.published().copied(),
+            updated: Some(*entry.updated()),
+        }
+    }
+
+    pub fn from_json(item: &json_feed::Item, feed_id: &str, feed_title: &str) -> Self {
+        let link = item.url.clone().or(item.external_url.clone());
+
+        let enclosures = item
+            .attachments
+            .iter()
+            .map(|attachment| Enclosure {
+                url: attachment.url.clone(),
+                mime_type: Some(attachment.mime_type.clone()),
+                length: attachment.size_in_bytes,
+                duration: attachment
+                    .duration_in_seconds
+                    .map(|seconds| format_duration(seconds as u64)),
+            })
+            .collect::<Vec<_>>();
+
+        let mut images: Vec<String> = item
+            .image
+            .iter()
+            .chain(item.banner_image.iter())
+            .cloned()
+            .collect();
+        images.extend(image_enclosures(&enclosures));
+
+        let id = match item.id() {
+            id if id.trim().is_empty() => fallback_id(link.as_deref(), item.title.as_deref()),
+            id => id,
+        };
+
+        Self {
+            id,
+            feed_id: feed_id.to_string(),
+            feed_title: feed_title.to_string(),
+            title: title_or_fallback(
+                item.title.as_deref().or(item.summary.as_deref()),
+                link.as_deref(),
+            ),
+            link,
+            authors: item
+                .authors()
+                .filter_map(|author| author.name.clone())
+                .collect(),
+            categories: item.tags.clone(),
+            summary: non_empty(item.summary.as_deref().map(text_to_html).as_deref()),
+            content: non_empty(
+                item.content_html
+                    .clone()
+                    .or(item.content_text.as_deref().map(text_to_html))
+                    .as_deref(),
+            ),
+            enclosures,
+            images,
+            published: item
+                .date_published
+                .as_deref()
+                .and_then(|date| DateTime::parse_from_rfc3339(date).ok()),
+            updated: item
+                .date_modified
+                .as_deref()
+                .and_then(|date| DateTime::parse_from_rfc3339(date).ok()),
+        }
+    }
+
+    // First enclosure that isn't an image, the one to play or download.
+    pub fn media_enclosure(&self) -> Option<&Enclosure> {
+        self.enclosures
+            .iter()
+            .find(|enclosure| enclosure.is_media())
+    }
+
+    // Date used for sorting and display.
+    pub fn date(&self) -> Option<DateTime<FixedOffset>> {
+        self.published.or(self.updated)
+    }
+
+    pub fn date_string(&self) -> String {
+        let Some(time) = self.date() else {
+            return String::new();
+        };
+        let now = chrono::offset::Local::now();
+
+        if time.year() == now.year() && time.month() == now.month() && time.day() == now.day() {
+            time.format("%H:%M").to_string()
+        } else {
+            time.format("%y-%m-%d %H:%M").to_string()
+        }
+    }
+
+    // Short HTML description for the popup, the summary if the feed provides one.
+    pub fn description(&self) -> &str {
+        self.summary
+            .as_deref()
+            .or(self.content.as_deref())
+            .unwrap_or_default()
+    }
+
+    // Full HTML body for the reader, e.g. RSS `content:encoded` or Atom `content`.
+    pub fn body(&self) -> &str {
+        self.content
+            .as_deref()
+            .or(self.summary.as_deref())
+            .unwrap_or_default()
+    }
+
+    pub fn image_url(&self) -> Option<&str> {
+        self.images.first().map(String::as_str)
+    }
+}
+
+// Two articles are the same if they come from the same feed and share their id,
+// regardless of any changes to their content.
+impl PartialEq for Article {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.feed_id == other.feed_id
+    }
+}
+
+impl Eq for Article {}
+
+fn non_empty(text: Option<&str>) -> Option<String> {
+    text.map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+// Hex encoded hash of link and title for entries without an id.
+fn fallback_id(link: Option<&str>, title: Option<&str>) -> String {
+    let bytes = link
+        .unwrap_or_default()
+        .bytes()
+        .chain([b'\n'])
+        .chain(title.unwrap_or_default().bytes());
+    format!("{:016x}", fnv1a(bytes))
+}
+
+// Escapes plain text so it can be rendered like the HTML of other feeds, keeping its line breaks.
+fn text_to_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+fn title_or_fallback(title: Option<&str>, link: Option<&str>) -> String {
+    non_empty(title)
+        .or(link.map(str::to_string))
+        .unwrap_or("(untitled)".to_string())
+}
+
+// Images from Media RSS `media:content` and `media:thumbnail` elements.
+// RSS and Atom have distinct extension types, `attrs` gets the attributes of either.
+fn media_images<E>(
+    extensions: &BTreeMap<String, BTreeMap<String, Vec<E>>>,
+    attrs: fn(&E) -> &BTreeMap<String, String>,
+) -> Vec<String> {
+    let Some(media) = extensions.get("media") else {
+        return Vec::new();
+    };
+    ["content", "thumbnail"]
+        .iter()
+        .filter_map(|name| media.get(*name))
+        .flatten()
+        .map(attrs)
+        .filter(|attrs| {
+            attrs
+                .get("medium")
+                .or(attrs.get("type"))
+                .is_none_or(|kind| kind.starts_with("image"))
+        })
+        .filter_map(|attrs| attrs.get("url").cloned())
+        .collect()
+}
+
+fn image_enclosures(enclosures: &[Enclosure]) -> impl Iterator<Item = String> + '_ {
+    enclosures
+        .iter()
+        .filter(|enclosure| !enclosure.is_media())
+        .map(|enclosure| enclosure.url.clone())
+}
+
+// Decodes `%XX` escapes, keeping them if they don't form UTF-8.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| text.to_string())
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, (seconds / 60) % 60, seconds % 60),
+    }
+}
@@ -0,0 +1,69 @@
+use crate::paths::write_file;
+use reqwest::header::{HeaderMap, ETAG, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf, time::SystemTime};
+
+// Validators of the last successful response, sent with the next request
+// so the server can answer with `304 Not Modified`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+}
+
+// On-disk cache of the last good response body of every feed.
+#[derive(Clone, Debug)]
+pub struct FeedCache {
+    dir: PathBuf,
+}
+
+impl FeedCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    // Returns the cached body of the feed together with its validators.
+    pub fn load(&self, url: &str) -> Option<(Validators, Vec<u8>)> {
+        let body = fs::read(self.file(url, "body")).ok()?;
+        let validators = fs::read_to_string(self.file(url, "json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Some((validators, body))
+    }
+
+    // Time of the last successful fetch, the cached body is only written then.
+    pub fn last_success(&self, url: &str) -> Option<SystemTime> {
+        fs::metadata(self.file(url, "body"))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    pub fn store(&self, url: &str, validators: &Validators, body: &[u8]) -> std::io::Result<()> {
+        write_file(&self.file(url, "body"), body)?;
+        write_file(&self.file(url, "json"), serde_json::to_string(validators)?)
+    }
+
+    fn file(&self, url: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!(
+            "{:016x}.{}",
+            crate::hash::fnv1a(url.bytes()),
+            extension
+        ))
+    }
+}
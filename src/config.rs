@@ -0,0 +1,93 @@
+use crate::fetcher::FetchConfig;
+use crate::keymap::{KeyConfig, Keymap};
+use crate::paths::Paths;
+use crate::store::Retention;
+use crate::theme::{Theme, ThemeConfig};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Settings read from `config.toml` in the configuration directory.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Minutes between two fetches of the same feed, feeds can override it in `feeds.json`.
+    pub refresh_interval: u64,
+    // Timeouts, retries and how many feeds are fetched at once.
+    pub fetch: FetchConfig,
+    // Show the list of subscriptions next to the articles on start.
+    pub sidebar: bool,
+    // Capture the mouse to click and scroll, otherwise the terminal handles it.
+    pub mouse: bool,
+    pub keys: KeyConfig,
+    // Bindings resolved from `keys` when loading.
+    #[serde(skip)]
+    pub keymap: Keymap,
+    // Name of a built-in theme or one of `themes`.
+    pub theme: Option<String>,
+    pub themes: BTreeMap<String, ThemeConfig>,
+    // Styles resolved from `theme` when loading.
+    #[serde(skip)]
+    pub styles: Theme,
+    // Command that plays enclosures, e.g. `mpv --no-video`, the URL or file is appended.
+    // Without one the system's default application is used.
+    pub player: Option<String>,
+    // Where downloaded enclosures are saved, a folder per feed is created inside.
+    pub download_dir: Option<PathBuf>,
+    // Which articles are removed from the database on start and by `ta-rss gc`.
+    pub retention: Retention,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            refresh_interval: 30,
+            fetch: FetchConfig::default(),
+            sidebar: true,
+            mouse: true,
+            keys: KeyConfig::default(),
+            keymap: Keymap::default(),
+            theme: None,
+            themes: BTreeMap::new(),
+            styles: Theme::load(None, &BTreeMap::new()).unwrap_or_default(),
+            player: None,
+            download_dir: None,
+            retention: Retention::default(),
+        }
+    }
+}
+
+impl Config {
+    // Reads the configuration, a missing file results in the defaults.
+    pub fn load(paths: &Paths) -> anyhow::Result<Self> {
+        let file = paths.config_file();
+        match std::fs::read_to_string(&file) {
+            Ok(content) => {
+                let invalid = |e: &dyn std::fmt::Display| {
+                    anyhow::anyhow!("Invalid config {}: {}", file.display(), e)
+                };
+                let mut config: Self = toml::from_str(&content).map_err(|e| invalid(&e))?;
+                config.keymap = Keymap::new(&config.keys).map_err(|e| invalid(&e))?;
+                config.styles = Theme::load(config.theme.as_deref(), &config.themes)
+                    .map_err(|e| invalid(&e))?;
+                config.download_dir = config.download_dir.map(|dir| expand_home(&dir));
+                Ok(config)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.refresh_interval.max(1) * 60)
+    }
+}
+
+// Replaces a leading `~` with the home directory.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
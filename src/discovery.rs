@@ -0,0 +1,284 @@
+use crate::feed::{parse_feed, FetchError};
+use crate::html::decode_entities;
+use futures::future::join_all;
+use reqwest::{Client, Url};
+use rustc_hash::FxHashSet;
+
+// Feed types announced by `<link rel="alternate">` elements.
+const FEED_TYPES: [&str; 3] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+];
+
+// Paths that commonly serve the feed of a site, tried if a page doesn't announce any.
+const COMMON_PATHS: [&str; 8] = [
+    "/feed",
+    "/rss",
+    "/index.xml",
+    "/feed.xml",
+    "/rss.xml",
+    "/atom.xml",
+    "/feed.json",
+    "/index.rss",
+];
+
+// A feed found for the URL given by the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub url: String,
+    pub title: String,
+}
+
+// Finds the feeds behind a URL, which is either a feed itself or a web page announcing feeds.
+// URLs without a scheme are tried with `https://`.
+pub async fn discover(client: &Client, url: &str) -> anyhow::Result<Vec<Candidate>> {
+    let url = url.trim();
+    let page_url = match Url::parse(url) {
+        Ok(page_url) if ["http", "https"].contains(&page_url.scheme()) => page_url,
+        Err(_) if !url.contains("://") && !url.is_empty() => {
+            Url::parse(&format!("https://{}", url)).map_err(|_| anyhow::anyhow!("Invalid URL"))?
+        }
+        _ => anyhow::bail!("Invalid URL"),
+    };
+
+    let response = client
+        .get(page_url.clone())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(FetchError::from)?;
+    // Relative links are resolved against the page the server redirected to
+    let base = response.url().clone();
+    let body = response.bytes().await.map_err(FetchError::from)?;
+
+    if let Ok(feed) = parse_feed(page_url.as_str(), &body) {
+        return Ok(vec![Candidate {
+            url: page_url.to_string(),
+            title: feed.title,
+        }]);
+    }
+
+    let mut urls = announced_feeds(&String::from_utf8_lossy(&body), &base);
+    if urls.is_empty() {
+        urls = COMMON_PATHS
+            .iter()
+            .filter_map(|path| base.join(path).ok())
+            .collect();
+    }
+
+    let candidates: Vec<Candidate> = join_all(urls.iter().map(|url| check(client, url)))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    if candidates.is_empty() {
+        anyhow::bail!("No feed found at {}", page_url);
+    }
+    Ok(candidates)
+}
+
+// Candidate for the URL if it serves a feed that can be parsed.
+async fn check(client: &Client, url: &Url) -> Option<Candidate> {
+    let response = client.get(url.clone()).send().await.ok()?;
+    let body = response.error_for_status().ok()?.bytes().await.ok()?;
+    let feed = parse_feed(url.as_str(), &body).ok()?;
+    Some(Candidate {
+        url: url.to_string(),
+        title: feed.title,
+    })
+}
+
+// Feeds announced by the page, resolved against its URL.
+// Pages may announce the same feed several times, it is returned once.
+fn announced_feeds(html: &str, base: &Url) -> Vec<Url> {
+    let mut urls: Vec<Url> = alternate_links(html)
+        .iter()
+        .filter_map(|href| base.join(href).ok())
+        .collect();
+    let mut seen = FxHashSet::default();
+    urls.retain(|url| seen.insert(url.clone()));
+    urls
+}
+
+// Targets of the `<link rel="alternate">` elements of an HTML page that point to feeds.
+fn alternate_links(html: &str) -> Vec<String> {
+    // Lowercasing ASCII keeps the byte offsets the same
+    let lowercase = html.to_ascii_lowercase();
+    let mut links = Vec::new();
+    let mut position = 0;
+    while let Some(start) = lowercase[position..].find("<link") {
+        let start = position + start + "<link".len();
+        let end = tag_end(&lowercase[start..]).map_or(lowercase.len(), |end| start + end);
+        position = end;
+
+        let attributes = attributes(&html[start..end]);
+        let value = |name: &str| {
+            attributes
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let is_alternate = value("rel").is_some_and(|rel| {
+            rel.split_ascii_whitespace()
+                .any(|rel| rel.eq_ignore_ascii_case("alternate"))
+        });
+        let is_feed = value("type").is_some_and(|kind| {
+            let kind = kind.split(';').next().unwrap_or_default();
+            FEED_TYPES.contains(&kind.trim().to_ascii_lowercase().as_str())
+        });
+        if let (true, true, Some(href)) = (is_alternate, is_feed, value("href")) {
+            if !href.trim().is_empty() && !links.iter().any(|link| link == href) {
+                links.push(href.trim().to_string());
+            }
+        }
+    }
+    links
+}
+
+// Offset of the `>` closing a tag, skipping those inside quoted values.
+// Falls back to the first `>` if a quote isn't closed.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut after_equals = false;
+    for (index, c) in tag.char_indices() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => continue,
+            None if c == '>' => return Some(index),
+            None if after_equals && matches!(c, '"' | '\'') => quote = Some(c),
+            None => {}
+        }
+        if !c.is_whitespace() {
+            after_equals = c == '=';
+        }
+    }
+    tag.find('>')
+}
+
+// Attributes of a tag like ` rel="alternate" href='/feed' hidden`, values are decoded.
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = tag.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        rest = rest[name_end..].trim_start();
+
+        let mut value = "";
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (raw, remainder) = match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after_equals[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or_default())
+                }
+                _ => {
+                    let end = after_equals
+                        .find(char::is_whitespace)
+                        .unwrap_or(after_equals.len());
+                    (&after_equals[..end], &after_equals[end..])
+                }
+            };
+            value = raw;
+            rest = remainder;
+        }
+        if !name.is_empty() {
+            attributes.push((name.to_string(), decode_entities(value)));
+        }
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feeds(html: &str) -> Vec<String> {
+        let base = Url::parse("https://example.com/blog/post.html").unwrap();
+        announced_feeds(html, &base)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn reads_quoted_and_unquoted_attributes() {
+        let html = r#"<head>
+            <link rel="alternate" type="application/rss+xml" href="https://example.com/rss">
+            <LINK REL='alternate' TYPE='application/atom+xml' HREF='https://example.com/atom'/>
+            <link rel=alternate type=application/feed+json href=https://example.com/json >
+            <link href = "https://example.com/spaced" type = "application/rss+xml" rel = "alternate">
+            <link rel="alternate" type="application/rss+xml; charset=utf-8"
+                  title="Posts &amp; comments" href="https://example.com/a?x=1&amp;y=2">
+            </head>"#;
+        assert_eq!(
+            feeds(html),
+            [
+                "https://example.com/rss",
+                "https://example.com/atom",
+                "https://example.com/json",
+                "https://example.com/spaced",
+                "https://example.com/a?x=1&y=2",
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_rel_tokens_and_feed_types() {
+        let html = r#"
+            <link rel="alternate feed" type="application/rss+xml" href="/both">
+            <link rel="ALTERNATE" type="APPLICATION/ATOM+XML" href="/upper">
+            <link rel="alternative" type="application/rss+xml" href="/wrong-rel">
+            <link rel="stylesheet alternate" type="text/css" href="/style.css">
+            <link rel="alternate" hreflang="de" href="/de/">
+            <link rel="alternate" type="application/rss+xml" href="  ">
+            <link rel="alternate" type="application/rss+xml">"#;
+        assert_eq!(
+            feeds(html),
+            ["https://example.com/both", "https://example.com/upper"]
+        );
+    }
+
+    #[test]
+    fn resolves_relative_links() {
+        let html = r#"
+            <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+            <link rel="alternate" type="application/rss+xml" href="comments.xml">
+            <link rel="alternate" type="application/rss+xml" href="../atom.xml">
+            <link rel="alternate" type="application/rss+xml" href="//cdn.example.net/feed">"#;
+        assert_eq!(
+            feeds(html),
+            [
+                "https://example.com/feed.xml",
+                "https://example.com/blog/comments.xml",
+                "https://example.com/atom.xml",
+                "https://cdn.example.net/feed",
+            ]
+        );
+    }
+
+    #[test]
+    fn returns_duplicates_once() {
+        let html = r#"
+            <link rel="alternate" type="application/rss+xml" href="/feed">
+            <link rel="alternate" type="application/atom+xml" href="/feed">
+            <link rel="alternate" type="application/rss+xml" href="https://example.com/feed">
+            <link rel="alternate" type="application/rss+xml" href="/other">"#;
+        assert_eq!(
+            feeds(html),
+            ["https://example.com/feed", "https://example.com/other"]
+        );
+    }
+
+    #[test]
+    fn allows_angle_brackets_in_quoted_values() {
+        let html = r#"<link rel="alternate" title="Posts > Comments" type="application/rss+xml"
+            href="/comments">"#;
+        assert_eq!(feeds(html), ["https://example.com/comments"]);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::article::Enclosure;
+use crate::hash::fnv1a;
+use reqwest::header::RANGE;
+use reqwest::{Client, StatusCode};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+// Time between two progress reports of a download.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+// A running download.
+#[derive(Clone, Debug)]
+pub struct Download {
+    pub file_name: String,
+    pub received: u64,
+    // Size of the whole file if the server sent it.
+    pub total: Option<u64>,
+}
+
+impl Download {
+    pub fn percent(&self) -> Option<u64> {
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| (self.received * 100 / total).min(100))
+    }
+}
+
+// Reported by the download tasks, keyed by the URL of the download.
+#[derive(Debug)]
+pub enum DownloadEvent {
+    Progress {
+        url: String,
+        received: u64,
+        total: Option<u64>,
+    },
+    Finished {
+        url: String,
+        path: PathBuf,
+    },
+    Failed {
+        url: String,
+        error: String,
+    },
+}
+
+// Downloads enclosures in the background.
+//
+// Files are written next to their target with a `.part` suffix and renamed once complete.
+// A download that finds a partial file continues where it stopped if the server supports ranges.
+pub struct Downloads {
+    pub active: BTreeMap<String, Download>,
+    client: Client,
+    sender: mpsc::UnboundedSender<DownloadEvent>,
+    receiver: mpsc::UnboundedReceiver<DownloadEvent>,
+}
+
+impl Downloads {
+    pub fn new(client: Client) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            active: BTreeMap::new(),
+            client,
+            sender,
+            receiver,
+        }
+    }
+
+    // Starts downloading the enclosure to `target` unless it is already being downloaded.
+    pub fn start(&mut self, enclosure: &Enclosure, target: PathBuf) -> bool {
+        let url = enclosure.url.as_str();
+        if self.active.contains_key(url) {
+            return false;
+        }
+        self.active.insert(
+            url.to_string(),
+            Download {
+                file_name: enclosure.file_name(),
+                received: 0,
+                total: None,
+            },
+        );
+
+        let (client, sender, url) = (self.client.clone(), self.sender.clone(), url.to_string());
+        tokio::spawn(async move {
+            let event = match download(&client, &url, &target, &sender).await {
+                Ok(()) => DownloadEvent::Finished { url, path: target },
+                Err(e) => DownloadEvent::Failed {
+                    url,
+                    error: e.to_string(),
+                },
+            };
+            let _ = sender.send(event);
+        });
+        true
+    }
+
+    // Applies the reported progress and returns the downloads that ended since the last call.
+    pub fn poll(&mut self) -> Vec<DownloadEvent> {
+        let mut ended = Vec::new();
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                DownloadEvent::Progress {
+                    url,
+                    received,
+                    total,
+                } => {
+                    if let Some(download) = self.active.get_mut(&url) {
+                        download.received = received;
+                        download.total = total;
+                    }
+                }
+                DownloadEvent::Finished { ref url, .. } | DownloadEvent::Failed { ref url, .. } => {
+                    self.active.remove(url);
+                    ended.push(event);
+                }
+            }
+        }
+        ended
+    }
+
+    // Summary for the status bar like `↓ episode.mp3 45%`, `None` if nothing is downloading.
+    pub fn status(&self) -> Option<String> {
+        let (first, others) = (self.active.values().next()?, self.active.len() - 1);
+        let progress = match first.percent() {
+            Some(percent) => format!("{}%", percent),
+            None => format_size(first.received),
+        };
+        Some(match others {
+            0 => format!("↓ {} {}", first.file_name, progress),
+            others => format!("↓ {} {} (+{} more)", first.file_name, progress, others),
+        })
+    }
+}
+
+async fn download(
+    client: &Client,
+    url: &str,
+    target: &Path,
+    sender: &mpsc::UnboundedSender<DownloadEvent>,
+) -> anyhow::Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut partial = target.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    let offset = fs::metadata(&partial)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let response = request.send().await?;
+    // The partial file already holds the whole file
+    if offset > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        fs::rename(&partial, target).await?;
+        return Ok(());
+    }
+    let mut response = response.error_for_status()?;
+
+    // Servers without support for ranges send the whole file again
+    let resumed = offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+    let mut received = if resumed { offset } else { 0 };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&partial)
+        .await?;
+    let total = response.content_length().map(|length| length + received);
+
+    let mut last_report = Instant::now();
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        received += chunk.len() as u64;
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            last_report = Instant::now();
+            let _ = sender.send(DownloadEvent::Progress {
+                url: url.to_string(),
+                received,
+                total,
+            });
+        }
+    }
+    file.flush().await?;
+    fs::rename(&partial, target).await?;
+    Ok(())
+}
+
+// Where an enclosure of the feed is saved, a folder per feed inside `dir`.
+// Hosts often name every episode like `audio.mp3`, so the name starts with a hash of the URL.
+pub fn target_path(dir: &Path, feed_title: &str, enclosure: &Enclosure) -> PathBuf {
+    let hash = fnv1a(enclosure.url.bytes()) as u32;
+    dir.join(sanitize(feed_title)).join(format!(
+        "{:08x}-{}",
+        hash,
+        sanitize(&enclosure.file_name())
+    ))
+}
+
+// Replaces characters that aren't allowed in file names.
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match name.trim_start_matches('.') {
+        "" => "download".to_string(),
+        name => name.to_string(),
+    }
+}
+
+// Size like `24.1 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enclosure(url: &str) -> Enclosure {
+        Enclosure {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn names_episodes_apart() {
+        let dir = Path::new("/podcasts");
+        let first = target_path(
+            dir,
+            "Show: Daily",
+            &enclosure("https://cdn.example/1/audio.mp3"),
+        );
+        let second = target_path(
+            dir,
+            "Show: Daily",
+            &enclosure("https://cdn.example/2/audio.mp3"),
+        );
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), Some(Path::new("/podcasts/Show_ Daily")));
+        assert_eq!(first.extension(), Some("mp3".as_ref()));
+        let name = first.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.ends_with("-audio.mp3"), "{name}");
+        assert_eq!(
+            first,
+            target_path(
+                dir,
+                "Show: Daily",
+                &enclosure("https://cdn.example/1/audio.mp3")
+            )
+        );
+    }
+}
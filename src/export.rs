@@ -0,0 +1,268 @@
+use crate::article::Article;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+// Output formats of exported articles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    // A line per article with date, feed and title.
+    Plain,
+    Json,
+    // Date, feed, title, link and feed URL separated by tabs, without a header.
+    Tsv,
+    Markdown,
+}
+
+// Which articles `ta-rss print` shows, all of them if nothing is given.
+#[derive(Args, Debug, Default)]
+pub struct Query {
+    /// Only articles of the feed with this URL, title or index from `list`
+    #[arg(long)]
+    pub feed: Option<String>,
+    /// Only articles of feeds in this folder or its subfolders
+    #[arg(long)]
+    pub folder: Option<String>,
+    /// Only articles from this date on, like `2024-03-01`, an RFC 3339 time or `7d` for the last week
+    #[arg(long, value_parser = parse_date)]
+    pub since: Option<DateTime<Utc>>,
+    /// Only articles before this date
+    #[arg(long, value_parser = parse_date)]
+    pub until: Option<DateTime<Utc>>,
+    /// Only unread articles
+    #[arg(long)]
+    pub unread: bool,
+    /// Only starred articles
+    #[arg(long)]
+    pub starred: bool,
+    /// Only articles whose title, feed, author or description contain the text
+    #[arg(long)]
+    pub search: Option<String>,
+    /// Print at most this many articles, the newest first
+    #[arg(long)]
+    pub limit: Option<usize>,
+}
+
+impl Query {
+    // Whether the article's date is inside `since` and `until`, articles without a date are
+    // only included if neither is given.
+    pub fn in_range(&self, article: &Article) -> bool {
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        let Some(date) = article.date().map(|date| date.with_timezone(&Utc)) else {
+            return false;
+        };
+        self.since.is_none_or(|since| date >= since) && self.until.is_none_or(|until| date < until)
+    }
+}
+
+// A date like `2024-03-01` (midnight in local time), an RFC 3339 time or a number of days
+// before now like `7d`.
+fn parse_date(text: &str) -> Result<DateTime<Utc>, String> {
+    let text = text.trim();
+    let date = match text
+        .strip_suffix('d')
+        .and_then(|days| days.parse::<u32>().ok())
+    {
+        Some(days) => chrono::Duration::try_days(days.into())
+            .and_then(|days| Utc::now().checked_sub_signed(days)),
+        None => DateTime::parse_from_rfc3339(text)
+            .map(|date| date.with_timezone(&Utc))
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                    .ok()?
+                    .and_hms_opt(0, 0, 0)?
+                    .and_local_timezone(Local)
+                    .earliest()
+                    .map(|date| date.with_timezone(&Utc))
+            }),
+    };
+    date.ok_or_else(|| "expected a date like 2024-03-01, an RFC 3339 time or 7d".to_string())
+}
+
+// An article as written to JSON.
+#[derive(Serialize)]
+struct ExportedArticle<'a> {
+    title: &'a str,
+    link: Option<&'a str>,
+    feed: &'a str,
+    feed_url: &'a str,
+    date: Option<String>,
+    authors: &'a [String],
+    categories: &'a [String],
+}
+
+pub fn write(articles: &[Article], format: Format) -> anyhow::Result<String> {
+    match format {
+        Format::Plain => Ok(plain(articles)),
+        Format::Json => json(articles),
+        Format::Tsv => Ok(tsv(articles)),
+        Format::Markdown => Ok(markdown(articles)),
+    }
+}
+
+// Lines like `2024-03-06 15:11  Feed — Title`, dates in local time.
+fn plain(articles: &[Article]) -> String {
+    articles
+        .iter()
+        .map(|article| {
+            let date = article
+                .date()
+                .map(|date| {
+                    date.with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            format!(
+                "{:<16}  {} — {}\n",
+                date,
+                single_line(&article.feed_title),
+                single_line(&article.title)
+            )
+        })
+        .collect()
+}
+
+fn tsv(articles: &[Article]) -> String {
+    articles
+        .iter()
+        .map(|article| {
+            let fields = [
+                article
+                    .date()
+                    .map(|date| date.to_rfc3339())
+                    .unwrap_or_default(),
+                single_line(&article.feed_title),
+                single_line(&article.title),
+                single_line(article.link.as_deref().unwrap_or_default()),
+                single_line(&article.feed_id),
+            ];
+            fields.join("\t") + "\n"
+        })
+        .collect()
+}
+
+// Replaces tabs and line breaks, which would split a field or record, with spaces.
+fn single_line(text: &str) -> String {
+    text.split(['\t', '\n', '\r'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn json(articles: &[Article]) -> anyhow::Result<String> {
+    let articles: Vec<ExportedArticle> = articles
+        .iter()
+        .map(|article| ExportedArticle {
+            title: &article.title,
+            link: article.link.as_deref(),
+            feed: &article.feed_title,
+            feed_url: &article.feed_id,
+            date: article.date().map(|date| date.to_rfc3339()),
+            authors: &article.authors,
+            categories: &article.categories,
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&articles)? + "\n")
+}
+
+// A list like `- [Title](https://…) — Feed, 2024-03-06`, one line per article.
+fn markdown(articles: &[Article]) -> String {
+    articles
+        .iter()
+        .map(|article| {
+            let title = escape_markdown(article.title.trim());
+            let mut line = match &article.link {
+                Some(link) => format!("- [{}](<{}>) — {}", title, link, article.feed_title),
+                None => format!("- {} — {}", title, article.feed_title),
+            };
+            if let Some(date) = article.date() {
+                line.push_str(&format!(", {}", date.format("%Y-%m-%d")));
+            }
+            line + "\n"
+        })
+        .collect()
+}
+
+// Escapes characters that would end the link text or start formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(date: Option<&str>) -> Article {
+        Article {
+            published: date.map(|date| DateTime::parse_from_rfc3339(date).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn utc(date: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date).unwrap().into()
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(
+            parse_date("2024-03-01T12:30:00+02:00"),
+            Ok(utc("2024-03-01T10:30:00Z"))
+        );
+        let midnight = NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .earliest()
+            .unwrap();
+        assert_eq!(parse_date(" 2024-03-01 "), Ok(midnight.with_timezone(&Utc)));
+
+        let week_ago = Utc::now() - chrono::Duration::days(7);
+        let parsed = parse_date("7d").unwrap();
+        assert!((parsed - week_ago).num_seconds().abs() < 5);
+
+        for text in ["", "d", "-7d", "4000000000d", "2024-13-01", "yesterday"] {
+            assert!(parse_date(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn filters_by_date_range() {
+        let query = Query {
+            since: Some(utc("2024-03-01T00:00:00Z")),
+            until: Some(utc("2024-03-02T00:00:00Z")),
+            ..Default::default()
+        };
+        assert!(query.in_range(&article(Some("2024-03-01T00:00:00Z"))));
+        assert!(query.in_range(&article(Some("2024-03-01T23:59:59Z"))));
+        assert!(!query.in_range(&article(Some("2024-03-02T00:00:00Z"))));
+        assert!(!query.in_range(&article(Some("2024-02-29T23:59:59Z"))));
+        // Offsets are compared as points in time
+        assert!(query.in_range(&article(Some("2024-03-02T01:00:00+02:00"))));
+        assert!(!query.in_range(&article(None)));
+    }
+
+    #[test]
+    fn keeps_undated_articles_without_range() {
+        assert!(Query::default().in_range(&article(None)));
+        let since = Query {
+            since: Some(utc("2024-03-01T00:00:00Z")),
+            ..Default::default()
+        };
+        assert!(since.in_range(&article(Some("2030-01-01T00:00:00Z"))));
+        assert!(!since.in_range(&article(None)));
+    }
+}
@@ -1,157 +1,297 @@
-use atom_syndication::Text;
-use chrono::Datelike;
-use reqwest::Client;
+use crate::article::Article;
+use crate::cache::{FeedCache, Validators};
+use crate::fetcher::Fetcher;
+use crate::json_feed::JsonFeed;
+use crate::scheduler::ScheduleHints;
+use reqwest::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use reqwest::{Client, StatusCode};
+use std::fmt;
+use std::time::SystemTime;
 use tokio::sync::mpsc;
 
+// Title and articles of a successfully parsed feed document.
+pub struct ParsedFeed {
+    pub title: String,
+    pub articles: Vec<Article>,
+    pub hints: ScheduleHints,
+}
+
+// Outcome of fetching the subscription with the given URL.
+pub struct FeedUpdate {
+    pub url: String,
+    pub result: Result<ParsedFeed, FetchError>,
+}
+
+// Reason why a feed could not be fetched.
 #[derive(Clone, Debug)]
-pub enum Feed {
-    Item(rss::Item),
-    Entry(atom_syndication::Entry),
+pub enum FetchError {
+    Http(StatusCode),
+    Timeout,
+    Tls(String),
+    Connection(String),
+    Parse(String),
 }
 
-impl Feed {
-    fn pub_date(&self) -> Option<atom_syndication::FixedDateTime> {
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Feed::Item(item) => Some(
-                chrono::DateTime::parse_from_rfc2822(item.pub_date().unwrap_or_default())
-                    .unwrap_or_default(),
-            ),
-            Feed::Entry(entry) => Some(*entry.updated()),
+            FetchError::Http(status) => write!(f, "HTTP {}", status),
+            FetchError::Timeout => write!(f, "Timed out"),
+            FetchError::Tls(message) => write!(f, "TLS error: {}", message),
+            FetchError::Connection(message) => write!(f, "Connection failed: {}", message),
+            FetchError::Parse(message) => write!(f, "Parse error: {}", message),
         }
     }
+}
 
-    pub fn source_name(&self) -> String {
+impl FetchError {
+    // Errors that may go away when trying again a bit later.
+    pub fn is_transient(&self) -> bool {
         match self {
-            Feed::Item(item) => match item.source() {
-                Some(source) => source.title().unwrap_or(source.url()).to_string(),
-                None => "a".to_string(),
-            },
-            Feed::Entry(entry) => match entry.source() {
-                Some(source) => source.title().value.to_string(),
-                None => "b".to_string(),
-            },
+            FetchError::Timeout | FetchError::Connection(_) => true,
+            FetchError::Http(status) => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            FetchError::Tls(_) | FetchError::Parse(_) => false,
         }
     }
+}
 
-    pub fn title(&self) -> String {
-        match self {
-            Feed::Item(item) => item.title().unwrap().to_string(),
-            Feed::Entry(entry) => entry.title().to_string(),
+impl std::error::Error for FetchError {}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return FetchError::Timeout;
+        }
+        if let Some(status) = error.status() {
+            return FetchError::Http(status);
         }
-    }
 
-    pub fn description(&self) -> String {
-        match self {
-            Feed::Item(item) => item.description().unwrap_or(" ").to_string(),
-            Feed::Entry(entry) => entry.summary().unwrap_or(&Text::plain(" ")).to_string(),
+        // reqwest has no dedicated TLS errors, they show up as the source of connection errors
+        let mut messages = Vec::new();
+        let mut source: Option<&dyn std::error::Error> = Some(&error);
+        while let Some(error) = source {
+            messages.push(error.to_string());
+            source = error.source();
+        }
+        let message = messages.last().cloned().unwrap_or_default();
+        let is_tls = messages.iter().any(|message| {
+            let message = message.to_lowercase();
+            message.contains("certificate") || message.contains("tls") || message.contains("ssl")
+        });
+
+        if is_tls {
+            FetchError::Tls(message)
+        } else {
+            FetchError::Connection(message)
         }
     }
+}
 
-    pub fn pub_date_string(&self) -> String {
-        let now = chrono::offset::Local::now();
-        let time = self.pub_date().unwrap_or_default();
+// Last known state of a subscription.
+#[derive(Clone, Debug, Default)]
+pub struct FeedStatus {
+    pub title: Option<String>,
+    pub error: Option<FetchError>,
+    // Also known from the cache before the feed was fetched in this session.
+    pub last_success: Option<SystemTime>,
+    // Whether a fetch finished in this session, until then the feed is pending.
+    pub fetched: bool,
+}
 
-        if time.year() == now.year() && time.month() == now.month() && time.day() == now.day() {
-            time.format("%H:%M").to_string()
-        } else {
-            time.format("%y-%m-%d %H:%M").to_string()
+impl FeedStatus {
+    pub fn update(&mut self, result: &Result<ParsedFeed, FetchError>) {
+        self.fetched = true;
+        match result {
+            Ok(feed) => {
+                self.title = Some(feed.title.clone());
+                self.error = None;
+                self.last_success = Some(SystemTime::now());
+            }
+            Err(error) => self.error = Some(error.clone()),
         }
     }
 
-    pub fn url(&self) -> String {
-        match self {
-            Feed::Item(item) => item.link().unwrap().to_string(),
-            Feed::Entry(entry) => entry.id().to_string(),
+    pub fn last_success_string(&self) -> String {
+        match self.last_success {
+            Some(time) => chrono::DateTime::<chrono::Local>::from(time)
+                .format("%y-%m-%d %H:%M")
+                .to_string(),
+            None => "never".to_string(),
         }
     }
+}
 
-    pub fn fetch_and_parse_feeds(url: &str, tx: &mpsc::Sender<Feed>) {
-        let url = url.to_string();
-        let client = Client::new();
-
-        let tx = tx.clone();
-        let client = client.clone();
-        tokio::spawn(async move {
-            let Ok(response) = client.get(url).send().await else {
-                return;
-            };
-            let Ok(result_as_bytes) = response.bytes().await else {
-                return;
-            };
-            if let Ok(channel) = rss::Channel::read_from(&result_as_bytes[..]) {
-                for mut item in channel.items {
-                    item.set_source(rss::Source {
-                        url: channel.link.to_string(),
-                        title: Some(channel.title.to_string()),
-                    });
-                    tx.send(Feed::Item(item)).await.unwrap_or_default();
-                }
-            } else if let Ok(feed) = atom_syndication::Feed::read_from(&result_as_bytes[..]) {
-                for mut entry in feed.entries {
-                    let title = Text {
-                        value: feed.title.value.to_string(),
-                        ..Default::default()
-                    };
-                    entry.set_source(Some(atom_syndication::Source {
-                        title,
-                        ..Default::default()
-                    }));
-                    tx.send(Feed::Entry(entry)).await.unwrap_or_default();
-                }
-            }
-        });
+// Parses an RSS, Atom or JSON Feed document fetched from `url`.
+pub fn parse_feed(url: &str, bytes: &[u8]) -> Result<ParsedFeed, FetchError> {
+    let rss_error = match rss::Channel::read_from(bytes) {
+        Ok(channel) => return Ok(parse_rss(url, channel)),
+        Err(e) => e.to_string(),
+    };
+    let atom_error = match atom_syndication::Feed::read_from(bytes) {
+        Ok(feed) => return Ok(parse_atom(url, feed)),
+        Err(e) => e.to_string(),
+    };
+    let json_error = match JsonFeed::read_from(bytes) {
+        Ok(feed) => return Ok(parse_json(url, feed)),
+        Err(e) => e.to_string(),
+    };
+
+    // Report the error of the parser that was most likely meant to read the document
+    let start = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_lowercase();
+    let message = if start.trim_start().starts_with('{') {
+        format!("JSON Feed: {}", json_error)
+    } else if start.contains("<feed") {
+        format!("Atom: {}", atom_error)
+    } else if start.contains("<html") {
+        "Not a feed but an HTML page".to_string()
+    } else {
+        format!("RSS: {}", rss_error)
+    };
+    Err(FetchError::Parse(message))
+}
+
+fn parse_rss(url: &str, channel: rss::Channel) -> ParsedFeed {
+    let title = feed_title(&channel.title, url);
+    let articles = channel
+        .items()
+        .iter()
+        .map(|item| Article::from_rss(item, url, &title))
+        .collect();
+    let hints = ScheduleHints::from_rss(&channel);
+    ParsedFeed {
+        title,
+        articles,
+        hints,
     }
+}
 
-    pub fn get_image_url(&self) -> Option<String> {
-        match self {
-            Feed::Item(item) => {
-                if let Some(media) = item.extensions().get("media") {
-                    if let Some(content) = media.get("content") {
-                        for ext in content {
-                            if ext.attrs().contains_key("url") {
-                                return Some(ext.attrs().get("url").unwrap().to_string());
-                            }
-                        }
-                    }
-                }
-                None
-            }
-            Feed::Entry(_entry) => None,
-        }
+fn parse_atom(url: &str, feed: atom_syndication::Feed) -> ParsedFeed {
+    let title = feed_title(&feed.title.value, url);
+    let articles = feed
+        .entries()
+        .iter()
+        .map(|entry| Article::from_atom(entry, url, &title))
+        .collect();
+    ParsedFeed {
+        title,
+        articles,
+        hints: ScheduleHints::default(),
     }
 }
 
-impl PartialOrd for Feed {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+fn parse_json(url: &str, feed: JsonFeed) -> ParsedFeed {
+    let title = feed_title(&feed.title, url);
+    let articles = feed
+        .items
+        .iter()
+        .map(|item| Article::from_json(item, url, &title))
+        .collect();
+    ParsedFeed {
+        title,
+        articles,
+        hints: ScheduleHints::default(),
     }
 }
 
-impl PartialEq for Feed {
-    fn eq(&self, other: &Self) -> bool {
-        self.pub_date() == other.pub_date() && self.source_name() == other.source_name()
+// Fetches the feed in the background and reports the outcome through `tx`.
+pub fn fetch_and_parse_feeds(
+    fetcher: &Fetcher,
+    url: &str,
+    tx: &mpsc::Sender<FeedUpdate>,
+    cache: &FeedCache,
+) {
+    let url = url.to_string();
+    let fetcher = fetcher.clone();
+
+    let tx = tx.clone();
+    let cache = cache.clone();
+    tokio::spawn(async move {
+        let result = fetcher.fetch(&url, Some(&cache)).await;
+        tx.send(FeedUpdate { url, result })
+            .await
+            .unwrap_or_default();
+    });
+}
+
+// Fetches a feed, asking the server to only send it if it changed since the cached response.
+pub async fn fetch(
+    client: &Client,
+    url: &str,
+    cache: Option<&FeedCache>,
+) -> Result<ParsedFeed, FetchError> {
+    let cached = cache.and_then(|cache| cache.load(url));
+
+    let mut request = client.get(url);
+    if let Some((validators, _)) = &cached {
+        if let Some(etag) = &validators.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().await?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some((_, body)) = cached {
+            return parse_feed(url, &body);
+        }
+    }
+
+    let response = response.error_for_status()?;
+    let validators = Validators::from_headers(response.headers());
+    let result_as_bytes = response.bytes().await?;
+    let feed = parse_feed(url, &result_as_bytes)?;
+    // Only bodies that could be parsed are cached
+    if let Some(cache) = cache {
+        let _ = cache.store(url, &validators, &result_as_bytes);
     }
+    Ok(feed)
 }
 
-impl Eq for Feed {}
+pub async fn check_url(fetcher: &Fetcher, url: &str) -> anyhow::Result<String> {
+    if reqwest::Url::parse(url).is_err() {
+        anyhow::bail!("Invalid URL");
+    }
+    let feed = fetcher.fetch(url, None).await?;
+    Ok(feed.title)
+}
 
-impl Ord for Feed {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.pub_date().cmp(&self.pub_date())
+fn feed_title(title: &str, url: &str) -> String {
+    match title.trim() {
+        "" => url.to_string(),
+        title => title.to_string(),
     }
 }
 
-pub async fn check_url(url: &str) -> anyhow::Result<String> {
-    if let Ok(response) = reqwest::get(url).await {
-        let result = response.bytes().await?;
-        if let Ok(channel) = rss::Channel::read_from(&result[..]) {
-            return Ok(channel.title);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_transient_errors() {
+        let transient = [
+            FetchError::Timeout,
+            FetchError::Connection("connection refused".to_string()),
+            FetchError::Http(StatusCode::INTERNAL_SERVER_ERROR),
+            FetchError::Http(StatusCode::SERVICE_UNAVAILABLE),
+            FetchError::Http(StatusCode::TOO_MANY_REQUESTS),
+        ];
+        for error in transient {
+            assert!(error.is_transient(), "{error}");
         }
-        if let Ok(feed) = atom_syndication::Feed::read_from(&result[..]) {
-            return Ok(feed.title.value);
+
+        let permanent = [
+            FetchError::Http(StatusCode::NOT_FOUND),
+            FetchError::Http(StatusCode::FORBIDDEN),
+            FetchError::Http(StatusCode::GONE),
+            FetchError::Tls("invalid certificate".to_string()),
+            FetchError::Parse("not a feed".to_string()),
+        ];
+        for error in permanent {
+            assert!(!error.is_transient(), "{error}");
         }
     }
-
-    let err = anyhow::Error::msg("Invalid URL");
-    Err(err)
 }
@@ -0,0 +1,160 @@
+use crate::cache::FeedCache;
+use crate::feed::{self, FetchError, ParsedFeed};
+use reqwest::{Client, Url};
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// Wait before the first retry, doubled for every further one up to `MAX_RETRY_DELAY`.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+// How feeds are fetched, the `[fetch]` section of the config.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FetchConfig {
+    // Feeds fetched at the same time.
+    pub concurrency: usize,
+    // Feeds of the same host fetched at the same time.
+    pub per_host: usize,
+    // Seconds to wait for a connection.
+    pub connect_timeout: u64,
+    // Seconds to wait for the server to send something.
+    pub timeout: u64,
+    // Attempts after timeouts, failed connections and server errors.
+    pub retries: u32,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            per_host: 2,
+            connect_timeout: 10,
+            timeout: 30,
+            retries: 2,
+        }
+    }
+}
+
+// Fetches feeds through one client, limiting how many are fetched at once.
+#[derive(Clone)]
+pub struct Fetcher {
+    client: Client,
+    config: FetchConfig,
+    slots: Arc<Semaphore>,
+    hosts: Arc<Mutex<FxHashMap<String, Arc<Semaphore>>>>,
+}
+
+impl Fetcher {
+    pub fn new(config: FetchConfig) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .user_agent(concat!("ta-rss/", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(Duration::from_secs(config.connect_timeout.max(1)))
+            .read_timeout(Duration::from_secs(config.timeout.max(1)))
+            .build()?;
+        Ok(Self {
+            client,
+            config,
+            slots: Arc::new(Semaphore::new(config.concurrency.max(1))),
+            hosts: Arc::default(),
+        })
+    }
+
+    // Client with the configured timeouts, shared by everything that goes to the network.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    // Fetches the feed once a slot is free, retrying transient errors with growing delays.
+    pub async fn fetch(
+        &self,
+        url: &str,
+        cache: Option<&FeedCache>,
+    ) -> Result<ParsedFeed, FetchError> {
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let _permits = self.acquire(url).await;
+                feed::fetch(&self.client, url, cache).await
+            };
+            match result {
+                Err(error) if error.is_transient() && attempt < self.config.retries => {
+                    tokio::time::sleep(retry_delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Waits for a slot of the host first, so feeds of a busy host don't hold global slots.
+    async fn acquire(&self, url: &str) -> (OwnedSemaphorePermit, OwnedSemaphorePermit) {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let host = {
+            let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+            hosts
+                .entry(host)
+                .or_insert_with(|| Arc::new(Semaphore::new(self.config.per_host.max(1))))
+                .clone()
+        };
+        // The semaphores are never closed
+        let host = host.acquire_owned().await.expect("semaphore closed");
+        let slot = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore closed");
+        (host, slot)
+    }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_RETRY_DELAY)
+}
+
+// Feeds fetched since the last time all fetches were done.
+#[derive(Debug, Default)]
+pub struct Progress {
+    pub fetched: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    pub fn started(&mut self, count: usize) {
+        self.total += count;
+    }
+
+    pub fn finished(&mut self, count: usize) {
+        self.fetched += count;
+        if self.fetched >= self.total {
+            *self = Self::default();
+        }
+    }
+
+    // Summary for the status bar like `fetched 37/80`, `None` if nothing is being fetched.
+    pub fn status(&self) -> Option<String> {
+        (self.total > 0).then(|| format!("fetched {}/{}", self.fetched, self.total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_retry_delay() {
+        assert_eq!(retry_delay(0), RETRY_DELAY);
+        assert_eq!(retry_delay(2), RETRY_DELAY * 4);
+        assert_eq!(retry_delay(10), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY_DELAY);
+    }
+}
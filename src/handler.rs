@@ -1,68 +1,315 @@
-use crate::app::{App, AppResult, AppState};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::add_feed::pasted_url;
+use crate::app::{App, AppResult, AppState, Focus};
+use crate::keymap::{Action, Mode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use std::time::{Duration, Instant};
+
+// Longest time between the clicks of a double click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+// Rows the reader scrolls per step of the mouse wheel.
+const WHEEL_ROWS: usize = 3;
 
 // Handles the key events and updates the state of [`App`].
 pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
-    global_key_events(key_event, app)?;
-    match app.app_state {
-        AppState::List => list_state(key_event, app)?,
-        AppState::Popup(_) => popup_state(key_event, app)?,
+    // Typed keys go into the search prompt instead of triggering actions
+    if app.search.as_ref().is_some_and(|search| search.editing) {
+        return search_prompt(key_event, app);
+    }
+    if let AppState::AddFeed(_) = app.app_state {
+        return add_feed_prompt(key_event, app);
+    }
+
+    let mode = mode(&app.app_state);
+    let Some(action) = app.config.keymap.action(mode, key_event) else {
+        // Links in the reader are opened by their number
+        if let (AppState::Reader(reader), KeyCode::Char(digit @ '1'..='9')) =
+            (&app.app_state, key_event.code)
+        {
+            let index = digit as usize - '1' as usize;
+            if let Some(url) = reader.document.links.get(index) {
+                let _open_error = open::that_in_background(url);
+            }
+        }
+        return Ok(());
+    };
+
+    match (action, &app.app_state) {
+        (Action::Quit, _) => app.quit(),
+        (_, AppState::List) => list_action(action, app),
+        (_, AppState::Popup(_)) => popup_action(action, app),
+        (_, AppState::Reader(_)) => reader_action(action, app),
+        (_, AppState::Feeds) => feeds_action(action, app),
         _ => {}
     };
 
     Ok(())
 }
 
-fn global_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
-    match key_event.code {
-        KeyCode::Char('q') => app.quit(),
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
-                app.quit();
-            }
+// Set of bindings that applies in the given state.
+pub fn mode(app_state: &AppState) -> Option<Mode> {
+    match app_state {
+        AppState::List => Some(Mode::List),
+        AppState::Popup(_) => Some(Mode::Popup),
+        AppState::Reader(_) => Some(Mode::Reader),
+        AppState::Feeds => Some(Mode::Feeds),
+        AppState::AddFeed(_) => None,
+    }
+}
+
+fn list_action(action: Action, app: &mut App) {
+    match action {
+        Action::SelectPrevious if app.focus == Focus::Sidebar => {
+            app.select_previous_sidebar_entry()
         }
+        Action::SelectNext if app.focus == Focus::Sidebar => app.select_next_sidebar_entry(),
+        Action::OpenPopup | Action::OpenReader if app.focus == Focus::Sidebar => app.toggle_focus(),
+        Action::SelectPrevious => app.select_previous(),
+        Action::SelectNext => app.select_next(),
+        Action::Back if app.search.is_some() => app.cancel_search(),
+        Action::Back => app.quit(),
+
+        Action::ToggleFocus => app.toggle_focus(),
+        Action::ToggleSidebar => app.toggle_sidebar(),
+
+        Action::ToggleRead => app.toggle_read(),
+        Action::ToggleStar => app.toggle_star(),
+        Action::Refresh => app.refresh_selected_feed(),
+        Action::RefreshAll => app.refresh_all(),
+        Action::OpenFeeds => app.open_feeds_view(),
+        Action::AddFeed => app.open_add_feed(""),
+        Action::PlayEnclosure => app.play_enclosure(),
+        Action::DownloadEnclosure => app.download_enclosure(),
+
+        Action::Search => app.start_search(),
+        Action::NextMatch if app.search.is_some() => app.select_next(),
+        Action::PreviousMatch if app.search.is_some() => app.select_previous(),
+
+        Action::OpenPopup => app.open_popup(),
+        Action::OpenReader => app.open_reader(),
         _ => {}
-    };
-    Ok(())
+    }
 }
 
-fn list_state(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+fn search_prompt(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
     match key_event.code {
+        KeyCode::Char('c') | KeyCode::Char('C') if key_event.modifiers == KeyModifiers::CONTROL => {
+            app.quit()
+        }
+        KeyCode::Char(c)
+            if !key_event
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            app.push_search_char(c)
+        }
+        KeyCode::Backspace => app.pop_search_char(),
+        KeyCode::Enter => app.confirm_search(),
+        KeyCode::Esc => app.cancel_search(),
         KeyCode::Up => app.select_previous(),
         KeyCode::Down => app.select_next(),
-        KeyCode::Esc => app.quit(),
+        _ => {}
+    }
 
-        KeyCode::Char(' ') => {
-            if let Some(selected) = app.list_state.selected() {
-                let selected_feed = app.feeds.get(selected).unwrap();
-                app.app_state = AppState::Popup(selected_feed.clone());
-            }
+    Ok(())
+}
+
+fn add_feed_prompt(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    let AppState::AddFeed(dialog) = &mut app.app_state else {
+        return Ok(());
+    };
+    match key_event.code {
+        KeyCode::Char('c') | KeyCode::Char('C') if key_event.modifiers == KeyModifiers::CONTROL => {
+            app.quit()
+        }
+        KeyCode::Char(c)
+            if !key_event
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            dialog.push_str(c.encode_utf8(&mut [0; 4]))
         }
+        KeyCode::Backspace => dialog.pop(),
+        KeyCode::Up => dialog.select_previous(),
+        KeyCode::Down => dialog.select_next(),
+        KeyCode::Enter => app.confirm_add_feed(),
+        KeyCode::Esc => app.app_state = AppState::List,
         _ => {}
     }
 
     Ok(())
 }
 
-fn popup_state(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
-    match key_event.code {
-        KeyCode::Char('o') | KeyCode::Char('O') => {
-            if let AppState::Popup(feed) = &app.app_state {
-                let url = feed.url();
-                let _open_error = open::that_in_background(url);
+fn popup_action(action: Action, app: &mut App) {
+    match action {
+        Action::OpenInBrowser => {
+            if let AppState::Popup(article) = &app.app_state {
+                if let Some(url) = &article.link {
+                    let _open_error = open::that_in_background(url);
+                }
             };
         }
-        KeyCode::Char(' ') => app.app_state = AppState::List,
-        KeyCode::Esc => app.app_state = AppState::List,
-        KeyCode::Up => app.select_previous(),
-        KeyCode::Down => app.select_next(),
+        Action::ToggleRead => app.toggle_read(),
+        Action::ToggleStar => app.toggle_star(),
+        Action::OpenReader => app.open_reader(),
+        Action::PlayEnclosure => app.play_enclosure(),
+        Action::DownloadEnclosure => app.download_enclosure(),
+        Action::Back => app.app_state = AppState::List,
+        Action::SelectPrevious => app.select_previous(),
+        Action::SelectNext => app.select_next(),
+        _ => {}
+    }
+}
+
+fn reader_action(action: Action, app: &mut App) {
+    match action {
+        Action::Back => return app.close_reader(),
+        Action::PlayEnclosure => return app.play_enclosure(),
+        Action::DownloadEnclosure => return app.download_enclosure(),
+        _ => {}
+    }
+    let AppState::Reader(reader) = &mut app.app_state else {
+        return;
+    };
+    match action {
+        Action::ScrollUp => reader.scroll_up(1),
+        Action::ScrollDown => reader.scroll_down(1),
+        Action::PageUp => reader.page_up(),
+        Action::PageDown => reader.page_down(),
+        Action::ScrollToTop => reader.scroll_to_top(),
+        Action::ScrollToBottom => reader.scroll_to_bottom(),
+        Action::OpenInBrowser => {
+            if let Some(url) = &reader.article.link {
+                let _open_error = open::that_in_background(url);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn feeds_action(action: Action, app: &mut App) {
+    match action {
+        Action::SelectPrevious => app.select_previous_subscription(),
+        Action::SelectNext => app.select_next_subscription(),
+        Action::Refresh => app.refresh_selected_subscription(),
+        Action::RefreshAll => app.refresh_all(),
+        Action::Back => app.app_state = AppState::List,
+        _ => {}
+    }
+}
+
+// Handles clicks and the mouse wheel.
+pub fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App) -> AppResult<()> {
+    let position = Position::new(mouse_event.column, mouse_event.row);
+    match mouse_event.kind {
+        MouseEventKind::ScrollUp => scroll(app, position, false),
+        MouseEventKind::ScrollDown => scroll(app, position, true),
+        MouseEventKind::Down(MouseButton::Left) => {
+            let double_click = app.last_click.is_some_and(|(time, column, row)| {
+                time.elapsed() < DOUBLE_CLICK_INTERVAL && (column, row) == (position.x, position.y)
+            });
+            // A third click starts a new double click
+            app.last_click = (!double_click).then_some((Instant::now(), position.x, position.y));
+            click(app, position, double_click);
+        }
         _ => {}
     }
 
     Ok(())
 }
 
-pub fn _handle_paste_event(app: &mut App, text: String) -> AppResult<()> {
-    app.app_state = AppState::PastedLink(text);
-    todo!("Paste event not implemented yet. Depends on crossterm feature 'bracketed-paste'.");
+fn scroll(app: &mut App, position: Position, down: bool) {
+    match &mut app.app_state {
+        AppState::Reader(reader) if down => reader.scroll_down(WHEEL_ROWS),
+        AppState::Reader(reader) => reader.scroll_up(WHEEL_ROWS),
+        AppState::Feeds if down => app.select_next_subscription(),
+        AppState::Feeds => app.select_previous_subscription(),
+        AppState::List if app.areas.sidebar.contains(position) => {
+            if down {
+                app.select_next_sidebar_entry()
+            } else {
+                app.select_previous_sidebar_entry()
+            }
+        }
+        AppState::List | AppState::Popup(_) if down => app.select_next(),
+        AppState::List | AppState::Popup(_) => app.select_previous(),
+        AppState::AddFeed(_) => {}
+    }
+}
+
+fn click(app: &mut App, position: Position, double_click: bool) {
+    match &app.app_state {
+        AppState::Reader(reader) => {
+            let link = reader
+                .link_areas
+                .iter()
+                .find(|(area, _)| area.contains(position))
+                .and_then(|&(_, index)| reader.document.links.get(index));
+            if let Some(url) = link {
+                let _open_error = open::that_in_background(url);
+            }
+        }
+        AppState::Popup(_) if !app.areas.popup.contains(position) => app.app_state = AppState::List,
+        AppState::List => {
+            if let Some(row) = clicked_row(app.areas.sidebar, position) {
+                app.focus = Focus::Sidebar;
+                let index = app.sidebar_state.offset() + row;
+                if index < app.sidebar.len() {
+                    app.select_sidebar_entry(index);
+                }
+            } else if let Some(row) = clicked_row(app.areas.list, position) {
+                app.focus = Focus::List;
+                let index = app.list_state.offset() + row;
+                if index < app.visible.len() {
+                    app.select(index);
+                    if double_click {
+                        app.open_popup();
+                    }
+                }
+            }
+        }
+        AppState::Feeds => {
+            // The first row of the table is its header
+            let row = clicked_row(app.areas.feeds, position).and_then(|row| row.checked_sub(1));
+            if let Some(row) = row {
+                let index = app.feeds_table_state.offset() + row;
+                if index < app.subscriptions.len() {
+                    app.feeds_table_state.select(Some(index));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// Row inside the borders of `area` the position is in.
+fn clicked_row(area: Rect, position: Position) -> Option<usize> {
+    let inner = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+    inner
+        .contains(position)
+        .then(|| (position.y - inner.y) as usize)
+}
+
+// Pasted text goes into the prompt being edited, otherwise it is offered as a feed to add.
+pub fn handle_paste_event(app: &mut App, text: String) -> AppResult<()> {
+    if app.search.as_ref().is_some_and(|search| search.editing) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            app.push_search_char(c);
+        }
+        return Ok(());
+    }
+
+    let url = pasted_url(&text);
+    match &mut app.app_state {
+        AppState::AddFeed(dialog) => dialog.push_str(url),
+        AppState::List | AppState::Feeds if !url.is_empty() => app.open_add_feed(url),
+        _ => {}
+    }
+
+    Ok(())
 }
@@ -0,0 +1,12 @@
+// 64 bit FNV-1a hash.
+//
+// Used where hashes are persisted and must not change between versions,
+// which the hashers of the standard library don't guarantee.
+pub fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
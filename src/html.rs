@@ -0,0 +1,482 @@
+use crate::theme::Theme;
+use html_parser::{Dom, Element, Node};
+use ratatui::{
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span, Text},
+};
+use reqwest::Url;
+
+// HTML converted into styled text for the terminal.
+#[derive(Clone, Debug, Default)]
+pub struct Document {
+    pub text: Text<'static>,
+    // Targets of the links, referenced as `[1]`, `[2]`, ... in the text.
+    pub links: Vec<String>,
+    // Where the references are in `text`, so the reader can make them clickable.
+    pub references: Vec<LinkReference>,
+}
+
+// A `[n]` in the text, positioned by its line and the columns it takes up in that line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkReference {
+    pub line: usize,
+    pub column: usize,
+    pub width: usize,
+    // Index into `links`.
+    pub link: usize,
+}
+
+impl Document {
+    // Converts HTML, or plain text, into a document styled by the theme.
+    // Relative links are resolved against `base`, markup the parser can't make sense of is stripped.
+    pub fn from_html(html: &str, base: Option<&str>, theme: &Theme) -> Self {
+        let renderer = Renderer {
+            base: base.and_then(|base| Url::parse(base).ok()),
+            code: theme.code,
+            link_reference: theme.link_reference,
+            ..Renderer::default()
+        };
+        renderer.render(html)
+    }
+
+    // Converts HTML into text without styles or link references, e.g. for searching.
+    pub fn plain_text(html: &str) -> String {
+        let renderer = Renderer {
+            plain: true,
+            ..Renderer::default()
+        };
+        let document = renderer.render(html);
+        document
+            .text
+            .lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Default)]
+struct Renderer {
+    lines: Vec<Line<'static>>,
+    current: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    // Indentation of nested lists and quotes, added in front of every line.
+    prefixes: Vec<Span<'static>>,
+    // Bullet or number of a list item that has not been written yet.
+    bullet: Option<String>,
+    // Counters of the enclosing lists, `None` for unordered lists.
+    lists: Vec<Option<usize>>,
+    links: Vec<String>,
+    references: Vec<LinkReference>,
+    base: Option<Url>,
+    code: Style,
+    link_reference: Style,
+    // Leaves out link references.
+    plain: bool,
+    preformatted: usize,
+    space_pending: bool,
+}
+
+impl Renderer {
+    fn render(mut self, html: &str) -> Document {
+        match Dom::parse(&protect_whitespace(html)) {
+            Ok(dom) if !has_unparsed_markup(&dom.children) => self.nodes(&dom.children),
+            _ => self.text(&strip_tags(html)),
+        }
+        self.finish()
+    }
+
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.text(&decode_entities(text)),
+                Node::Element(element) => self.element(element),
+                Node::Comment(_) => {}
+            }
+        }
+    }
+
+    fn element(&mut self, element: &Element) {
+        let style = self.style();
+        let name = element.name.to_lowercase();
+        match name.as_str() {
+            "script" | "style" | "head" | "title" => {}
+            "br" => self.line_break(),
+            "hr" => {
+                self.block_break();
+                self.push(Span::styled("────────", Style::default().dim()));
+                self.block_break();
+            }
+            "p" | "div" | "section" | "article" | "figure" | "figcaption" | "table" | "tr"
+            | "dl" | "dt" | "dd" => {
+                self.block_break();
+                self.nodes(&element.children);
+                self.block_break();
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let style = match name.as_str() {
+                    "h1" | "h2" => style.bold().underlined(),
+                    _ => style.bold(),
+                };
+                self.block_break();
+                self.styled(style, &element.children);
+                self.block_break();
+            }
+            "strong" | "b" => self.styled(style.bold(), &element.children),
+            "em" | "i" | "cite" => self.styled(style.italic(), &element.children),
+            "u" | "ins" => self.styled(style.underlined(), &element.children),
+            "s" | "del" | "strike" => self.styled(style.crossed_out(), &element.children),
+            "code" | "kbd" | "samp" => self.styled(style.patch(self.code), &element.children),
+            "pre" => {
+                self.block_break();
+                self.preformatted += 1;
+                self.prefixes.push(Span::raw("  "));
+                self.styled(style.patch(self.code), &element.children);
+                self.prefixes.pop();
+                self.preformatted -= 1;
+                self.block_break();
+            }
+            "blockquote" => {
+                self.block_break();
+                self.prefixes
+                    .push(Span::styled("│ ", Style::default().dim()));
+                self.styled(style.italic(), &element.children);
+                self.flush_line();
+                self.prefixes.pop();
+                self.block_break();
+            }
+            "ul" | "ol" => {
+                self.block_break();
+                self.lists.push((name == "ol").then_some(0));
+                self.nodes(&element.children);
+                self.lists.pop();
+                self.block_break();
+            }
+            "li" => {
+                self.flush_line();
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", number)
+                    }
+                    _ => "• ".to_string(),
+                };
+                let indentation = " ".repeat(bullet.chars().count());
+                self.bullet = Some(bullet);
+                self.prefixes.push(Span::raw(indentation));
+                self.nodes(&element.children);
+                self.flush_line();
+                self.prefixes.pop();
+                self.bullet = None;
+            }
+            "a" => {
+                self.styled(style.underlined(), &element.children);
+                let href = element.attributes.get("href").filter(|_| !self.plain);
+                if let Some(Some(href)) = href {
+                    let mut href = decode_entities(href);
+                    if let Some(url) = self.base.as_ref().and_then(|base| base.join(&href).ok()) {
+                        href = url.to_string();
+                    }
+                    let link = match self.links.iter().position(|link| *link == href) {
+                        Some(index) => index,
+                        None => {
+                            self.links.push(href);
+                            self.links.len() - 1
+                        }
+                    };
+                    self.push(self.reference_span(link));
+                    let width = self.current.last().map_or(0, Span::width);
+                    let column = self.current.iter().map(Span::width).sum::<usize>() - width;
+                    self.references.push(LinkReference {
+                        line: self.lines.len(),
+                        column,
+                        width,
+                        link,
+                    });
+                }
+            }
+            "img" => {
+                let alt = element
+                    .attributes
+                    .get("alt")
+                    .cloned()
+                    .flatten()
+                    .filter(|alt| !alt.trim().is_empty());
+                let text = match alt {
+                    Some(alt) => format!("[Image: {}]", decode_entities(alt.trim())),
+                    None => "[Image]".to_string(),
+                };
+                self.push(Span::styled(text, Style::default().dim()));
+            }
+            _ => self.nodes(&element.children),
+        }
+    }
+
+    fn styled(&mut self, style: Style, nodes: &[Node]) {
+        self.styles.push(style);
+        self.nodes(nodes);
+        self.styles.pop();
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    // The `[n]` pointing to the link with the index.
+    fn reference_span(&self, link: usize) -> Span<'static> {
+        Span::styled(format!("[{}]", link + 1), self.link_reference)
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.preformatted > 0 {
+            for (index, line) in text.split('\n').enumerate() {
+                if index > 0 {
+                    self.line_break();
+                }
+                if !line.is_empty() {
+                    self.push(Span::styled(line.to_string(), self.style()));
+                }
+            }
+            return;
+        }
+
+        // Whitespace is collapsed like a browser does
+        if text.starts_with(char::is_whitespace) {
+            self.space_pending = true;
+        }
+        let mut words = text.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            self.push(Span::styled(word.to_string(), self.style()));
+            self.space_pending = words.peek().is_some();
+        }
+        if text.ends_with(char::is_whitespace) {
+            self.space_pending = true;
+        }
+    }
+
+    // Appends a span to the current line, starting the line with its prefixes if needed.
+    fn push(&mut self, span: Span<'static>) {
+        if self.current.is_empty() {
+            self.current.extend(self.prefixes.iter().cloned());
+            if let Some(bullet) = self.bullet.take() {
+                // The bullet replaces the indentation of its own list item
+                self.current.pop();
+                self.current.push(Span::raw(bullet));
+            }
+        } else if self.space_pending {
+            self.current.push(Span::raw(" "));
+        }
+        self.space_pending = false;
+        self.current.push(span);
+    }
+
+    fn line_break(&mut self) {
+        if self.current.is_empty() {
+            self.current.extend(self.prefixes.iter().cloned());
+        }
+        self.flush_line();
+    }
+
+    fn flush_line(&mut self) {
+        if !self.current.is_empty() {
+            self.lines
+                .push(Line::from(std::mem::take(&mut self.current)));
+        }
+        self.space_pending = false;
+    }
+
+    // Ends the current block, separating it from the next one by an empty line.
+    // Blocks within lists aren't separated to keep the items together.
+    fn block_break(&mut self) {
+        self.flush_line();
+        if self.lists.is_empty() && self.lines.last().is_some_and(|line| line.width() > 0) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn finish(mut self) -> Document {
+        self.block_break();
+        if !self.links.is_empty() {
+            self.lines.push(Line::styled(
+                "Links",
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+            for (index, link) in self.links.iter().enumerate() {
+                let reference = self.reference_span(index);
+                self.references.push(LinkReference {
+                    line: self.lines.len(),
+                    column: 0,
+                    width: reference.width(),
+                    link: index,
+                });
+                self.lines.push(Line::from(vec![
+                    reference,
+                    Span::raw(" "),
+                    Span::raw(link.clone()),
+                ]));
+            }
+        }
+        while self.lines.last().is_some_and(|line| line.width() == 0) {
+            self.lines.pop();
+        }
+
+        Document {
+            text: Text::from(self.lines),
+            links: self.links,
+            references: self.references,
+        }
+    }
+}
+
+// The parser drops text consisting only of whitespace, like the space in `<b>a</b> <i>b</i>`.
+// Encoding it as entities keeps the words apart.
+fn protect_whitespace(html: &str) -> String {
+    let mut protected = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(end) = rest.find('>') {
+        protected.push_str(&rest[..=end]);
+        rest = &rest[end + 1..];
+        let text = rest.find('<').map_or(rest, |start| &rest[..start]);
+        if !text.is_empty() && text.chars().all(char::is_whitespace) {
+            for c in text.chars() {
+                protected.push_str(&format!("&#{};", c as u32));
+            }
+            rest = &rest[text.len()..];
+        }
+    }
+    protected.push_str(rest);
+    protected
+}
+
+// The parser keeps unclosed tags as plain text.
+fn has_unparsed_markup(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Text(text) => text
+            .split('<')
+            .skip(1)
+            .any(|rest| rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/')),
+        Node::Element(element) => has_unparsed_markup(&element.children),
+        Node::Comment(_) => false,
+    })
+}
+
+// Removes everything between `<` and `>`, used when the HTML can't be parsed.
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for (index, c) in html.char_indices() {
+        match c {
+            '<' if html[index + 1..]
+                .starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!') =>
+            {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text)
+}
+
+// Decodes named entities commonly found in feeds and all numeric entities.
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| &rest[1..=end]);
+        let replacement = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "ndash" => Some('–'),
+            "mdash" => Some('—'),
+            "hellip" => Some('…'),
+            "lsquo" => Some('‘'),
+            "rsquo" => Some('’'),
+            "ldquo" => Some('“'),
+            "rdquo" => Some('”'),
+            "copy" => Some('©'),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+
+        match (entity, replacement) {
+            (Some(entity), Some(replacement)) => {
+                decoded.push(replacement);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_uppercase_tags() {
+        let document = Document::from_html(
+            "<H1>Title</H1><OL><LI>first</LI></OL>",
+            None,
+            &Theme::default(),
+        );
+        let lines: Vec<String> = document.text.lines.iter().map(Line::to_string).collect();
+        assert_eq!(lines, ["Title", "", "1. first"]);
+        let heading = &document.text.lines[0].spans[0];
+        assert!(heading.style.add_modifier.contains(Modifier::UNDERLINED));
+    }
+
+    #[test]
+    fn styles_code_and_references_by_theme() {
+        let theme = Theme::light();
+        let document = Document::from_html(
+            r#"<p><code>x</code> <a href="https://example.com">y</a></p><pre>z</pre>"#,
+            None,
+            &theme,
+        );
+        let span = |line: usize, content: &str| {
+            document.text.lines[line]
+                .spans
+                .iter()
+                .find(|span| span.content == content)
+                .map(|span| span.style)
+        };
+        assert_eq!(span(0, "x"), Some(theme.code));
+        assert_eq!(span(0, "[1]"), Some(theme.link_reference));
+        assert_eq!(span(2, "z"), Some(theme.code));
+        assert_eq!(span(5, "[1]"), Some(theme.link_reference));
+    }
+}
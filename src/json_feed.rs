@@ -0,0 +1,92 @@
+use serde::Deserialize;
+
+// A feed in the JSON Feed format, see https://www.jsonfeed.org/version/1.1/
+#[derive(Clone, Debug, Deserialize)]
+pub struct JsonFeed {
+    pub version: String,
+    pub title: String,
+    #[serde(default)]
+    pub home_page_url: Option<String>,
+    #[serde(default)]
+    pub items: Vec<Item>,
+}
+
+impl JsonFeed {
+    pub fn read_from(bytes: &[u8]) -> anyhow::Result<Self> {
+        let feed: JsonFeed = serde_json::from_slice(bytes)?;
+        if !feed.version.starts_with("https://jsonfeed.org/version/") {
+            anyhow::bail!("Unknown JSON Feed version {}", feed.version);
+        }
+        Ok(feed)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Item {
+    // Version 1.0 allowed numbers as ids as well.
+    pub id: serde_json::Value,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub external_url: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub content_html: Option<String>,
+    #[serde(default)]
+    pub content_text: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub banner_image: Option<String>,
+    #[serde(default)]
+    pub date_published: Option<String>,
+    #[serde(default)]
+    pub date_modified: Option<String>,
+    // Version 1.1 replaced `author` with `authors`.
+    #[serde(default)]
+    pub authors: Vec<Author>,
+    #[serde(default)]
+    pub author: Option<Author>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+impl Item {
+    pub fn id(&self) -> String {
+        match &self.id {
+            serde_json::Value::String(id) => id.to_string(),
+            id => id.to_string(),
+        }
+    }
+
+    pub fn authors(&self) -> impl Iterator<Item = &Author> {
+        self.authors.iter().chain(self.author.iter())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Author {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub avatar: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Attachment {
+    pub url: String,
+    pub mime_type: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub size_in_bytes: Option<u64>,
+    #[serde(default)]
+    pub duration_in_seconds: Option<f64>,
+}
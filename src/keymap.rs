@@ -0,0 +1,514 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Something a key can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    Back,
+    SelectPrevious,
+    SelectNext,
+    OpenPopup,
+    OpenReader,
+    OpenInBrowser,
+    ToggleRead,
+    ToggleStar,
+    ToggleFocus,
+    ToggleSidebar,
+    Refresh,
+    RefreshAll,
+    OpenFeeds,
+    AddFeed,
+    PlayEnclosure,
+    DownloadEnclosure,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ScrollToTop,
+    ScrollToBottom,
+}
+
+// Views with their own set of bindings, keys bound in `Global` work everywhere.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Global,
+    List,
+    Popup,
+    Reader,
+    Feeds,
+}
+
+impl Mode {
+    const ALL: [Mode; 5] = [
+        Mode::Global,
+        Mode::List,
+        Mode::Popup,
+        Mode::Reader,
+        Mode::Feeds,
+    ];
+
+    fn actions(self) -> &'static [Action] {
+        use Action::*;
+        match self {
+            Mode::Global => &[Quit],
+            Mode::List => &[
+                Back,
+                SelectPrevious,
+                SelectNext,
+                OpenPopup,
+                OpenReader,
+                ToggleRead,
+                ToggleStar,
+                ToggleFocus,
+                ToggleSidebar,
+                Refresh,
+                RefreshAll,
+                OpenFeeds,
+                AddFeed,
+                Search,
+                NextMatch,
+                PreviousMatch,
+                PlayEnclosure,
+                DownloadEnclosure,
+            ],
+            Mode::Popup => &[
+                Back,
+                SelectPrevious,
+                SelectNext,
+                OpenReader,
+                OpenInBrowser,
+                ToggleRead,
+                ToggleStar,
+                PlayEnclosure,
+                DownloadEnclosure,
+            ],
+            Mode::Reader => &[
+                Back,
+                ScrollUp,
+                ScrollDown,
+                PageUp,
+                PageDown,
+                ScrollToTop,
+                ScrollToBottom,
+                OpenInBrowser,
+                PlayEnclosure,
+                DownloadEnclosure,
+            ],
+            Mode::Feeds => &[Back, SelectPrevious, SelectNext, Refresh, RefreshAll],
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    // Same spelling as in the config file, e.g. `select-next`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, c) in format!("{:?}", self).chars().enumerate() {
+            if c.is_uppercase() && index > 0 {
+                write!(f, "-")?;
+            }
+            write!(f, "{}", c.to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Mode::Global => "global",
+            Mode::List => "list",
+            Mode::Popup => "popup",
+            Mode::Reader => "reader",
+            Mode::Feeds => "feeds",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Built-in set of bindings the `[keys]` section of the config starts from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    #[default]
+    Default,
+    // The default bindings plus hjkl-style navigation.
+    Vim,
+}
+
+// The `[keys]` section of `config.toml`.
+// Binding an action replaces the preset's keys for it, an empty list unbinds it.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyConfig {
+    pub preset: Preset,
+    pub global: BTreeMap<Action, Keys>,
+    pub list: BTreeMap<Action, Keys>,
+    pub popup: BTreeMap<Action, Keys>,
+    pub reader: BTreeMap<Action, Keys>,
+    pub feeds: BTreeMap<Action, Keys>,
+}
+
+// A single key or a list of keys.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn iter(&self) -> impl Iterator<Item = &String> {
+        match self {
+            Keys::One(key) => std::slice::from_ref(key).iter(),
+            Keys::Many(keys) => keys.iter(),
+        }
+    }
+}
+
+impl KeyConfig {
+    fn bindings(&self, mode: Mode) -> &BTreeMap<Action, Keys> {
+        match mode {
+            Mode::Global => &self.global,
+            Mode::List => &self.list,
+            Mode::Popup => &self.popup,
+            Mode::Reader => &self.reader,
+            Mode::Feeds => &self.feeds,
+        }
+    }
+}
+
+// A key together with its modifiers, written like `ctrl-c`, `shift-tab` or `pagedown`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        // A single character is always a key, even `-`
+        let (modifier_names, key) = match text.rsplit_once('-') {
+            Some((modifiers, key)) if !key.is_empty() && text.chars().count() > 1 => {
+                (modifiers.split('-').collect(), key)
+            }
+            _ => (Vec::new(), text),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => anyhow::bail!("Unknown modifier `{}` in `{}`", name, text),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(number @ 1..=12) => KeyCode::F(number),
+                    _ => anyhow::bail!("Unknown key `{}`", text),
+                },
+            },
+        };
+
+        Ok(Self::normalized(code, modifiers))
+    }
+
+    // Shift is part of the character itself, `shift-a` and `A` are the same key.
+    // Terminals can't tell `ctrl-c` from `ctrl-C`, so these are the same as well.
+    fn normalized(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        let (code, modifiers) = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => (
+                KeyCode::Char(c.to_ascii_lowercase()),
+                modifiers - KeyModifiers::SHIFT,
+            ),
+            KeyCode::Char(c) if shift => (
+                KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers - KeyModifiers::SHIFT,
+            ),
+            KeyCode::Tab if shift => (KeyCode::BackTab, modifiers - KeyModifiers::SHIFT),
+            KeyCode::BackTab => (code, modifiers - KeyModifiers::SHIFT),
+            _ => (code, modifiers),
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(event: KeyEvent) -> Self {
+        Self::normalized(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if !self.modifiers.is_empty() => write!(f, "{}", c.to_uppercase()),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+// Bindings of the default preset, the vim preset adds `VIM_BINDINGS`.
+const DEFAULT_BINDINGS: &[(Mode, Action, &[&str])] = &[
+    (Mode::Global, Action::Quit, &["q", "ctrl-c"]),
+    (Mode::List, Action::SelectPrevious, &["up"]),
+    (Mode::List, Action::SelectNext, &["down"]),
+    (Mode::List, Action::OpenPopup, &["space"]),
+    (Mode::List, Action::OpenReader, &["enter"]),
+    (Mode::List, Action::ToggleRead, &["m", "M"]),
+    (Mode::List, Action::ToggleStar, &["*"]),
+    (Mode::List, Action::ToggleFocus, &["tab"]),
+    (Mode::List, Action::ToggleSidebar, &["b", "B"]),
+    (Mode::List, Action::Refresh, &["r"]),
+    (Mode::List, Action::RefreshAll, &["R"]),
+    (Mode::List, Action::OpenFeeds, &["f", "F"]),
+    (Mode::List, Action::AddFeed, &["a", "A"]),
+    (Mode::List, Action::Search, &["/"]),
+    (Mode::List, Action::NextMatch, &["n"]),
+    (Mode::List, Action::PreviousMatch, &["N"]),
+    (Mode::List, Action::PlayEnclosure, &["p", "P"]),
+    (Mode::List, Action::DownloadEnclosure, &["d", "D"]),
+    (Mode::List, Action::Back, &["esc"]),
+    (Mode::Popup, Action::SelectPrevious, &["up"]),
+    (Mode::Popup, Action::SelectNext, &["down"]),
+    (Mode::Popup, Action::OpenReader, &["enter"]),
+    (Mode::Popup, Action::OpenInBrowser, &["o", "O"]),
+    (Mode::Popup, Action::ToggleRead, &["m", "M"]),
+    (Mode::Popup, Action::ToggleStar, &["*"]),
+    (Mode::Popup, Action::PlayEnclosure, &["p", "P"]),
+    (Mode::Popup, Action::DownloadEnclosure, &["d", "D"]),
+    (Mode::Popup, Action::Back, &["space", "esc"]),
+    (Mode::Reader, Action::ScrollUp, &["up"]),
+    (Mode::Reader, Action::ScrollDown, &["down"]),
+    (Mode::Reader, Action::PageUp, &["pageup"]),
+    (Mode::Reader, Action::PageDown, &["pagedown", "space"]),
+    (Mode::Reader, Action::ScrollToTop, &["home"]),
+    (Mode::Reader, Action::ScrollToBottom, &["end"]),
+    (Mode::Reader, Action::OpenInBrowser, &["o", "O"]),
+    (Mode::Reader, Action::PlayEnclosure, &["p", "P"]),
+    (Mode::Reader, Action::DownloadEnclosure, &["d", "D"]),
+    (Mode::Reader, Action::Back, &["esc", "backspace"]),
+    (Mode::Feeds, Action::SelectPrevious, &["up"]),
+    (Mode::Feeds, Action::SelectNext, &["down"]),
+    (Mode::Feeds, Action::Refresh, &["r"]),
+    (Mode::Feeds, Action::RefreshAll, &["R"]),
+    (Mode::Feeds, Action::Back, &["f", "F", "esc"]),
+];
+
+const VIM_BINDINGS: &[(Mode, Action, &[&str])] = &[
+    (Mode::List, Action::SelectPrevious, &["k"]),
+    (Mode::List, Action::SelectNext, &["j"]),
+    (Mode::List, Action::OpenReader, &["l"]),
+    (Mode::Popup, Action::SelectPrevious, &["k"]),
+    (Mode::Popup, Action::SelectNext, &["j"]),
+    (Mode::Popup, Action::OpenReader, &["l"]),
+    (Mode::Popup, Action::Back, &["h"]),
+    (Mode::Reader, Action::ScrollUp, &["k"]),
+    (Mode::Reader, Action::ScrollDown, &["j"]),
+    (Mode::Reader, Action::PageUp, &["ctrl-u", "ctrl-b"]),
+    (Mode::Reader, Action::PageDown, &["ctrl-d", "ctrl-f"]),
+    (Mode::Reader, Action::ScrollToTop, &["g"]),
+    (Mode::Reader, Action::ScrollToBottom, &["G"]),
+    (Mode::Reader, Action::Back, &["h"]),
+    (Mode::Feeds, Action::SelectPrevious, &["k"]),
+    (Mode::Feeds, Action::SelectNext, &["j"]),
+];
+
+// Maps keys to actions per mode.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    // In the order of the preset, so hints show the preset's keys first.
+    bindings: Vec<(Mode, KeyChord, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&KeyConfig::default()).expect("the default preset is valid")
+    }
+}
+
+impl Keymap {
+    // Applies the configured bindings to the preset and rejects keys bound twice.
+    pub fn new(config: &KeyConfig) -> anyhow::Result<Self> {
+        let preset: &[_] = match config.preset {
+            Preset::Default => &[],
+            Preset::Vim => VIM_BINDINGS,
+        };
+        let mut bindings = Vec::new();
+        for &(mode, action, keys) in DEFAULT_BINDINGS.iter().chain(preset) {
+            for key in keys {
+                bindings.push((mode, KeyChord::parse(key)?, action));
+            }
+        }
+
+        for mode in Mode::ALL {
+            for (&action, keys) in config.bindings(mode) {
+                if !mode.actions().contains(&action) {
+                    anyhow::bail!("`{}` can't be bound in {} mode", action, mode);
+                }
+                bindings.retain(|&(m, _, a)| m != mode || a != action);
+                for key in keys.iter() {
+                    bindings.push((mode, KeyChord::parse(key)?, action));
+                }
+            }
+        }
+
+        let keymap = Self { bindings };
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+
+    fn check_conflicts(&self) -> anyhow::Result<()> {
+        for (index, &(mode, chord, action)) in self.bindings.iter().enumerate() {
+            for &(other_mode, other_chord, other_action) in &self.bindings[index + 1..] {
+                let overlapping =
+                    mode == other_mode || mode == Mode::Global || other_mode == Mode::Global;
+                if overlapping && chord == other_chord && action != other_action {
+                    anyhow::bail!(
+                        "`{}` is bound to both `{}` in {} mode and `{}` in {} mode",
+                        chord,
+                        action,
+                        mode,
+                        other_action,
+                        other_mode
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Action bound to the key in the given mode, global bindings take precedence.
+    pub fn action(&self, mode: Option<Mode>, event: KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from(event);
+        [Some(Mode::Global), mode]
+            .into_iter()
+            .flatten()
+            .find_map(|mode| {
+                self.bindings
+                    .iter()
+                    .find(|&&(m, c, _)| m == mode && c == chord)
+                    .map(|&(_, _, action)| action)
+            })
+    }
+
+    // Keys bound to the action, in the given mode or globally.
+    pub fn keys(&self, mode: Mode, action: Action) -> impl Iterator<Item = KeyChord> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |&&(m, _, a)| (m == mode || m == Mode::Global) && a == action)
+            .map(|&(_, chord, _)| chord)
+    }
+
+    // Hint like `↑/↓: Navigate` with the first key of every action, `None` if one is unbound.
+    pub fn hint(&self, mode: Mode, actions: &[Action], label: &str) -> Option<String> {
+        let keys = actions
+            .iter()
+            .map(|&action| self.keys(mode, action).next().map(|key| key.to_string()))
+            .collect::<Option<Vec<_>>>()?;
+        Some(format!("{}: {}", keys.join("/"), label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord { code, modifiers }
+    }
+
+    #[test]
+    fn parses_chords() {
+        let none = KeyModifiers::NONE;
+        let cases = [
+            ("q", chord(KeyCode::Char('q'), none)),
+            ("-", chord(KeyCode::Char('-'), none)),
+            ("space", chord(KeyCode::Char(' '), none)),
+            ("Enter", chord(KeyCode::Enter, none)),
+            ("pagedown", chord(KeyCode::PageDown, none)),
+            ("f5", chord(KeyCode::F(5), none)),
+            ("ctrl-c", chord(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            (
+                "Control-Alt-x",
+                chord(
+                    KeyCode::Char('x'),
+                    KeyModifiers::CONTROL | KeyModifiers::ALT,
+                ),
+            ),
+            ("alt-up", chord(KeyCode::Up, KeyModifiers::ALT)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(KeyChord::parse(text).unwrap(), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn normalizes_shift() {
+        let parse = |text| KeyChord::parse(text).unwrap();
+        assert_eq!(parse("shift-a"), parse("A"));
+        assert_eq!(parse("ctrl-C"), parse("ctrl-c"));
+        assert_eq!(parse("ctrl-shift-c"), parse("ctrl-c"));
+        assert_eq!(parse("shift-tab"), parse("backtab"));
+
+        let event = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from(event), parse("G"));
+        let event = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from(event), parse("backtab"));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        for text in ["", "hyper-a", "ctrl-", "pgdn", "f13", "f0"] {
+            assert!(KeyChord::parse(text).is_err(), "{text}");
+        }
+    }
+}
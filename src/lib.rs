@@ -2,15 +2,37 @@ use app::{App, AppResult};
 use clap::Parser;
 use clap::Subcommand;
 use event::{Event, EventHandler};
-use handler::{_handle_paste_event, handle_key_events};
+use handler::{handle_key_events, handle_mouse_events, handle_paste_event};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
+use std::path::PathBuf;
 use tui::Tui;
 
+pub mod add_feed;
 pub mod app;
+pub mod article;
+pub mod cache;
+pub mod config;
+pub mod discovery;
+pub mod download;
 pub mod event;
+pub mod export;
 pub mod feed;
+pub mod fetcher;
 pub mod handler;
+pub mod hash;
+pub mod html;
+pub mod json_feed;
+pub mod keymap;
+pub mod opml;
+pub mod paths;
+pub mod reader;
+pub mod scheduler;
+pub mod search;
+pub mod sidebar;
+pub mod store;
+pub mod subscription;
+pub mod theme;
 pub mod tui;
 pub mod ui;
 
@@ -21,7 +43,10 @@ pub async fn start_tui(mut app: App) -> AppResult<()> {
     let terminal = Terminal::new(backend)?;
     let events = EventHandler::new(100);
     let mut tui = Tui::new(terminal, events);
-    tui.init()?;
+    tui.init(app.config.mouse)?;
+
+    // Show the articles of the last session until the feeds are fetched again.
+    app.load_stored_articles();
 
     // Start the main loop.
     while app.running {
@@ -29,9 +54,9 @@ pub async fn start_tui(mut app: App) -> AppResult<()> {
         match tui.events.next().await? {
             Event::Tick => app.tick(),
             Event::Key(key_event) => handle_key_events(key_event, &mut app)?,
-            Event::Mouse(_) => {}
+            Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, &mut app)?,
             Event::Resize(_, _) => {}
-            Event::Paste(text) => _handle_paste_event(&mut app, text)?,
+            Event::Paste(text) => handle_paste_event(&mut app, text)?,
         }
         // Render the user interface.
         tui.draw(&mut app)?;
@@ -46,6 +71,10 @@ pub async fn start_tui(mut app: App) -> AppResult<()> {
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 pub struct Cli {
+    /// Keep the configuration, database and cache in this directory
+    #[arg(long, global = true, value_name = "DIR")]
+    pub config: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -54,8 +83,48 @@ pub struct Cli {
 pub enum Commands {
     /// Add an URL of a feed
     Add { url: String },
-    /// Remove URL
+    /// Remove a feed by its URL, title or index from `list`
     Remove { url: String },
+    /// Put a feed, given by its URL, title or index, into a folder like `Work` or `Work/News`
+    Tag { feed: String, folder: String },
+    /// Take a feed out of a folder
+    Untag { feed: String, folder: String },
+    /// Show a feed under another title, leave out the title to use the feed's own again
+    Rename { feed: String, title: Option<String> },
     /// List feeds
     List,
+    /// Import feeds from an OPML file
+    Import {
+        file: PathBuf,
+        /// Add the feeds without checking that they can be fetched
+        #[arg(long)]
+        no_verify: bool,
+    },
+    /// Export feeds as OPML to a file or stdout
+    Export { file: Option<PathBuf> },
+    /// Print the starred articles
+    Starred {
+        #[arg(long, value_enum, default_value_t = export::Format::Json)]
+        format: export::Format,
+    },
+    /// Fetch every feed once into the database, exiting with an error if any feed fails
+    Fetch,
+    /// Print stored articles, the newest first
+    Print {
+        #[command(flatten)]
+        query: export::Query,
+        #[arg(long, value_enum, default_value_t = export::Format::Plain)]
+        format: export::Format,
+    },
+    /// Fetch every feed once and report its status
+    Check,
+    /// Remove old articles from the database, starred articles are kept
+    Gc {
+        /// Remove articles older than this many days, instead of `retention.days` of the config
+        #[arg(long)]
+        days: Option<u64>,
+        /// Keep only this many articles per feed, instead of `retention.per_feed` of the config
+        #[arg(long)]
+        per_feed: Option<usize>,
+    },
 }
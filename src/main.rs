@@ -1,5 +1,8 @@
 use clap::Parser;
 use ta_rss::app::{App, AppResult};
+use ta_rss::config::Config;
+use ta_rss::export;
+use ta_rss::paths::Paths;
 use ta_rss::Commands;
 use ta_rss::{start_tui, Cli};
 
@@ -9,8 +12,12 @@ async fn main() -> AppResult<()> {
     // Parse cli commands
     let cli = Cli::parse();
 
+    // Resolve where feeds and state are stored
+    let paths = Paths::new(cli.config)?;
+    let config = Config::load(&paths)?;
+
     // Create a new instance of the application
-    let mut app = App::new().await;
+    let mut app = App::new(paths, config).await?;
 
     // Match on cli commands
     // If no command is given, start the user interface
@@ -19,11 +26,89 @@ async fn main() -> AppResult<()> {
             Ok(title) => println!("Added feed: {}", title),
             Err(e) => eprintln!("Failed to add feed: {}", e),
         },
-        Some(Commands::Remove { url }) => match app.remove_feed(&url) {
+        Some(Commands::Remove { url }) => match app.remove_feed(&url).await {
             Ok(title) => println!("Removed feed: {}", title),
             Err(e) => eprintln!("Failed to remove feed: {}", e),
         },
+        Some(Commands::Tag { feed, folder }) => match app.tag_feed(&feed, &folder).await {
+            Ok((title, folder)) => println!("Added {} to {}", title, folder),
+            Err(e) => eprintln!("Failed to tag feed: {}", e),
+        },
+        Some(Commands::Untag { feed, folder }) => match app.untag_feed(&feed, &folder).await {
+            Ok((title, folder)) => println!("Removed {} from {}", title, folder),
+            Err(e) => eprintln!("Failed to untag feed: {}", e),
+        },
+        Some(Commands::Rename { feed, title }) => {
+            if let Err(e) = app.rename_feed(&feed, title.as_deref()).await {
+                eprintln!("Failed to rename feed: {}", e);
+            }
+        }
         Some(Commands::List) => app.print_feeds(),
+        Some(Commands::Starred { format }) => match app.export_starred(format) {
+            Ok(output) => print!("{}", output),
+            Err(e) => eprintln!("Failed to export starred articles: {}", e),
+        },
+        Some(Commands::Fetch) => match app.fetch_feeds().await {
+            Ok(failed) if failed.is_empty() => {}
+            Ok(failed) => {
+                for (subscription, error) in &failed {
+                    eprintln!(
+                        "ERROR {}: {}\n      {}",
+                        subscription.display_name(),
+                        error,
+                        subscription.url
+                    );
+                }
+                eprintln!(
+                    "{} of {} feeds failed",
+                    failed.len(),
+                    app.subscriptions.len()
+                );
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to store the articles: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Print { query, format }) => {
+            match app
+                .query_articles(&query)
+                .await
+                .and_then(|articles| export::write(&articles, format))
+            {
+                Ok(output) => print!("{}", output),
+                Err(e) => {
+                    eprintln!("Failed to print articles: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Commands::Check) => {
+            let failed = app.check_feeds().await;
+            if failed > 0 {
+                eprintln!("{} of {} feeds failed", failed, app.subscriptions.len());
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Gc { days, per_feed }) => match app.gc(days, per_feed) {
+            Ok(removed) => println!("Removed {} articles", removed),
+            Err(e) => eprintln!("Failed to clean up the database: {}", e),
+        },
+        Some(Commands::Import { file, no_verify }) => {
+            match app.import_opml(&file, !no_verify).await {
+                Ok(count) => println!("Imported {} feeds", count),
+                Err(e) => eprintln!("Failed to import feeds: {}", e),
+            }
+        }
+        Some(Commands::Export { file }) => match app.export_opml(file.as_deref()) {
+            Ok(()) => {
+                if let Some(file) = file {
+                    println!("Exported feeds to {}", file.display());
+                }
+            }
+            Err(e) => eprintln!("Failed to export feeds: {}", e),
+        },
         None => {
             start_tui(app).await?;
         }
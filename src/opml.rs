@@ -0,0 +1,303 @@
+use crate::subscription::Subscription;
+use quick_xml::events::{BytesDecl, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+
+// Reads all feeds from an OPML document.
+//
+// Outlines without an `xmlUrl` are treated as folders, the folder path of a feed
+// is stored in its `folders`. A feed listed in several folders is returned once with all of them.
+// A `text` differing from the `title` of a feed is its custom title.
+pub fn parse(content: &str) -> anyhow::Result<Vec<Subscription>> {
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+
+    let mut subscriptions = Vec::new();
+    // Names of the enclosing outlines, `None` for outlines that are feeds themselves
+    let mut outlines: Vec<Option<String>> = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) if element.name().as_ref() == b"outline" => {
+                let outline = Outline::from_element(&element, &reader)?;
+                match outline.xml_url.clone() {
+                    Some(url) => {
+                        let subscription = outline.into_subscription(url, &outlines);
+                        merge_into(&mut subscriptions, subscription);
+                        outlines.push(None);
+                    }
+                    None => outlines.push(outline.name()),
+                }
+            }
+            Event::Empty(element) if element.name().as_ref() == b"outline" => {
+                let outline = Outline::from_element(&element, &reader)?;
+                if let Some(url) = outline.xml_url.clone() {
+                    let subscription = outline.into_subscription(url, &outlines);
+                    merge_into(&mut subscriptions, subscription);
+                }
+            }
+            Event::End(element) if element.name().as_ref() == b"outline" => {
+                outlines.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(subscriptions)
+}
+
+// Creates an OPML document of the given subscriptions nested by their folders,
+// a feed in several folders is listed in each of them.
+pub fn write(subscriptions: &[Subscription]) -> anyhow::Result<String> {
+    let mut root = Folder::default();
+    for subscription in subscriptions {
+        if subscription.folders.is_empty() {
+            root.feeds.push(subscription);
+        }
+        for path in &subscription.folders {
+            root.get_or_insert(path).feeds.push(subscription);
+        }
+    }
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer
+        .create_element("opml")
+        .with_attribute(("version", "2.0"))
+        .write_inner_content(|writer| {
+            writer
+                .create_element("head")
+                .write_inner_content(|writer| {
+                    writer
+                        .create_element("title")
+                        .write_text_content(BytesText::new("ta-rss subscriptions"))?;
+                    Ok::<(), quick_xml::Error>(())
+                })?;
+            writer
+                .create_element("body")
+                .write_inner_content(|writer| root.write_content(writer))?;
+            Ok::<(), quick_xml::Error>(())
+        })?;
+
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+struct Outline {
+    text: Option<String>,
+    title: Option<String>,
+    xml_url: Option<String>,
+}
+
+impl Outline {
+    fn from_element(element: &BytesStart, reader: &Reader<&[u8]>) -> anyhow::Result<Self> {
+        let mut text = None;
+        let mut title = None;
+        let mut xml_url = None;
+        for attribute in element.attributes() {
+            let attribute = attribute?;
+            let value = attribute
+                .decode_and_unescape_value(reader)?
+                .trim()
+                .to_string();
+            if value.is_empty() {
+                continue;
+            }
+            match attribute.key.as_ref() {
+                b"text" => text = Some(value),
+                b"title" => title = Some(value),
+                b"xmlUrl" => xml_url = Some(value),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            text,
+            title,
+            xml_url,
+        })
+    }
+
+    fn name(&self) -> Option<String> {
+        self.title.clone().or_else(|| self.text.clone())
+    }
+
+    fn into_subscription(self, url: String, outlines: &[Option<String>]) -> Subscription {
+        let path = outlines.iter().flatten().cloned().collect::<Vec<_>>();
+        let custom_title = self
+            .text
+            .clone()
+            .filter(|text| self.title.as_ref().is_some_and(|title| title != text));
+        Subscription {
+            title: self.name(),
+            custom_title,
+            folders: if path.is_empty() {
+                Vec::new()
+            } else {
+                vec![path.join("/")]
+            },
+            ..Subscription::new(&url)
+        }
+    }
+}
+
+// Adds the folders of a feed that is already listed to the earlier entry.
+fn merge_into(subscriptions: &mut Vec<Subscription>, subscription: Subscription) {
+    match subscriptions
+        .iter_mut()
+        .find(|known| known.url == subscription.url)
+    {
+        Some(known) => {
+            merge_folders(known, subscription.folders);
+        }
+        None => subscriptions.push(subscription),
+    }
+}
+
+// Adds the folders the subscription isn't in yet, returns whether there were any.
+pub fn merge_folders(subscription: &mut Subscription, folders: Vec<String>) -> bool {
+    let mut added = false;
+    for folder in folders {
+        if !subscription.folders.contains(&folder) {
+            subscription.folders.push(folder);
+            added = true;
+        }
+    }
+    added
+}
+
+#[derive(Default)]
+struct Folder<'a> {
+    name: String,
+    folders: Vec<Folder<'a>>,
+    feeds: Vec<&'a Subscription>,
+}
+
+impl<'a> Folder<'a> {
+    fn get_or_insert(&mut self, path: &str) -> &mut Folder<'a> {
+        let Some(name) = path.split('/').next().filter(|name| !name.is_empty()) else {
+            return self;
+        };
+        let rest = path.split_once('/').map_or("", |(_, rest)| rest);
+
+        let index = match self.folders.iter().position(|folder| folder.name == name) {
+            Some(index) => index,
+            None => {
+                self.folders.push(Folder {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                self.folders.len() - 1
+            }
+        };
+        self.folders[index].get_or_insert(rest)
+    }
+
+    fn write_content(&self, writer: &mut Writer<Vec<u8>>) -> quick_xml::Result<()> {
+        for folder in &self.folders {
+            writer
+                .create_element("outline")
+                .with_attribute(("text", folder.name.as_str()))
+                .with_attribute(("title", folder.name.as_str()))
+                .write_inner_content(|writer| folder.write_content(writer))?;
+        }
+        // `text` is shown by readers, `title` keeps the feed's own title next to a custom one
+        for feed in &self.feeds {
+            let mut outline = writer
+                .create_element("outline")
+                .with_attribute(("type", "rss"))
+                .with_attribute(("text", feed.display_name()));
+            if let Some(title) = &feed.title {
+                outline = outline.with_attribute(("title", title.as_str()));
+            }
+            outline
+                .with_attribute(("xmlUrl", feed.url.as_str()))
+                .write_empty()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(url: &str, title: &str, folders: &[&str]) -> Subscription {
+        Subscription {
+            title: Some(title.to_string()),
+            folders: folders.iter().map(|folder| folder.to_string()).collect(),
+            ..Subscription::new(url)
+        }
+    }
+
+    #[test]
+    fn parses_nested_folders() {
+        let content = r#"<?xml version="1.0"?>
+            <opml version="2.0"><body>
+              <outline text="Loose" xmlUrl="https://a.example/feed"/>
+              <outline text="Work">
+                <outline text="News">
+                  <outline title="Daily" text="Daily" xmlUrl="https://b.example/feed"/>
+                </outline>
+                <outline text="Weekly" xmlUrl="https://c.example/feed"/>
+              </outline>
+              <outline text="Fun">
+                <outline text="Daily" xmlUrl="https://b.example/feed"/>
+              </outline>
+              <outline title="Fetched" text="Renamed" xmlUrl="https://d.example/feed"/>
+            </body></opml>"#;
+        assert_eq!(
+            parse(content).unwrap(),
+            [
+                subscription("https://a.example/feed", "Loose", &[]),
+                subscription("https://b.example/feed", "Daily", &["Work/News", "Fun"]),
+                subscription("https://c.example/feed", "Weekly", &["Work"]),
+                Subscription {
+                    custom_title: Some("Renamed".to_string()),
+                    ..subscription("https://d.example/feed", "Fetched", &[])
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trips_folders() {
+        let subscriptions = [
+            subscription("https://a.example/feed", "Loose", &[]),
+            subscription("https://b.example/feed", "Daily", &["Work/News", "Fun"]),
+            subscription("https://c.example/feed", "Weekly & more", &["Work"]),
+            subscription("https://d.example/feed", "Deep", &["Work/News/Local"]),
+            Subscription {
+                custom_title: Some("Renamed".to_string()),
+                ..subscription("https://e.example/feed", "Fetched", &["Fun"])
+            },
+        ];
+        let written = write(&subscriptions).unwrap();
+        let mut parsed = parse(&written).unwrap();
+        parsed.sort_by(|a, b| a.url.cmp(&b.url));
+        assert_eq!(parsed, subscriptions);
+    }
+
+    #[test]
+    fn merges_folders_of_known_feeds() {
+        let mut subscriptions = vec![subscription("https://a.example/feed", "A", &["Work"])];
+        merge_into(
+            &mut subscriptions,
+            subscription("https://a.example/feed", "A", &["Work"]),
+        );
+        merge_into(
+            &mut subscriptions,
+            subscription("https://a.example/feed", "A", &["Fun"]),
+        );
+        merge_into(
+            &mut subscriptions,
+            subscription("https://b.example/feed", "B", &[]),
+        );
+        assert_eq!(
+            subscriptions,
+            [
+                subscription("https://a.example/feed", "A", &["Work", "Fun"]),
+                subscription("https://b.example/feed", "B", &[]),
+            ]
+        );
+    }
+}
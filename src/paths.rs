@@ -0,0 +1,119 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const APP_NAME: &str = "ta-rss";
+
+// Locations of the files ta-rss reads and writes.
+//
+// Configuration lives in `$XDG_CONFIG_HOME/ta-rss`, state like the read articles
+// in `$XDG_DATA_HOME/ta-rss` and disposable data in `$XDG_CACHE_HOME/ta-rss`.
+// A directory given with `--config` holds everything, so separate profiles don't share state.
+#[derive(Clone, Debug)]
+pub struct Paths {
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+    pub cache_dir: PathBuf,
+}
+
+impl Paths {
+    // Resolves the directories, everything is kept in `config_dir` if given.
+    pub fn new(config_dir: Option<PathBuf>) -> anyhow::Result<Self> {
+        if let Some(config_dir) = config_dir {
+            return Ok(Self {
+                data_dir: config_dir.clone(),
+                cache_dir: config_dir.join("cache"),
+                config_dir,
+            });
+        }
+
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| {
+                anyhow::anyhow!("Could not determine the configuration directory, use --config")
+            })?
+            .join(APP_NAME);
+        let data_dir = dirs::data_dir()
+            .map(|dir| dir.join(APP_NAME))
+            .unwrap_or_else(|| config_dir.clone());
+        let cache_dir = dirs::cache_dir()
+            .map(|dir| dir.join(APP_NAME))
+            .unwrap_or_else(|| data_dir.join("cache"));
+
+        let paths = Self {
+            config_dir,
+            data_dir,
+            cache_dir,
+        };
+        paths.migrate();
+        Ok(paths)
+    }
+
+    pub fn feeds_file(&self) -> PathBuf {
+        self.config_dir.join("feeds.json")
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join("config.toml")
+    }
+
+    // Default location of downloaded enclosures.
+    pub fn download_dir(&self) -> PathBuf {
+        dirs::download_dir()
+            .map(|dir| dir.join(APP_NAME))
+            .unwrap_or_else(|| self.data_dir.join("downloads"))
+    }
+
+    pub fn feed_cache_dir(&self) -> PathBuf {
+        self.cache_dir.join("feeds")
+    }
+
+    pub fn database_file(&self) -> PathBuf {
+        self.data_dir.join("articles.db")
+    }
+
+    // Read state of older versions, moved into the database on start.
+    pub fn read_articles_file(&self) -> PathBuf {
+        self.data_dir.join("read.json")
+    }
+
+    // Older versions kept their files next to the executable.
+    // Copy them over once, the originals stay untouched as the directory might be read-only.
+    fn migrate(&self) {
+        if self.feeds_file().exists() {
+            return;
+        }
+        let Some(exe_dir) = env::current_exe()
+            .ok()
+            .and_then(|exe_path| exe_path.parent().map(Path::to_path_buf))
+        else {
+            return;
+        };
+
+        for (old_file, new_file) in [
+            (exe_dir.join("feeds.json"), self.feeds_file()),
+            (exe_dir.join("read.json"), self.read_articles_file()),
+        ] {
+            if old_file.is_file() && !new_file.exists() {
+                match copy_file(&old_file, &new_file) {
+                    Ok(()) => {
+                        eprintln!("Migrated {} to {}", old_file.display(), new_file.display())
+                    }
+                    Err(e) => eprintln!("Failed to migrate {}: {}", old_file.display(), e),
+                }
+            }
+        }
+    }
+}
+
+// Writes `contents` to `path`, creating missing parent directories.
+pub fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+fn copy_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    write_file(to, fs::read(from)?)
+}
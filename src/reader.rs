@@ -0,0 +1,259 @@
+use crate::article::Article;
+use crate::html::{Document, LinkReference};
+use crate::theme::Theme;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthStr;
+
+// Full-screen view of an article's content.
+#[derive(Debug)]
+pub struct Reader {
+    pub article: Article,
+    pub document: Document,
+    // First visible row of the wrapped content.
+    pub scroll: usize,
+    // Rows of the wrapped content and of the area showing it, updated while rendering.
+    pub content_height: usize,
+    pub page_height: usize,
+    // Content wrapped for the last width it was shown at.
+    pub layout: Layout,
+    // Screen areas of the visible link references and the index of their link.
+    pub link_areas: Vec<(Rect, usize)>,
+    // Whether closing the reader returns to the popup instead of the list.
+    pub from_popup: bool,
+}
+
+impl Reader {
+    pub fn new(article: Article, from_popup: bool, theme: &Theme) -> Self {
+        let document = Document::from_html(article.body(), article.link.as_deref(), theme);
+        Self {
+            article,
+            document,
+            scroll: 0,
+            content_height: 0,
+            page_height: 0,
+            layout: Layout::default(),
+            link_areas: Vec::new(),
+            from_popup,
+        }
+    }
+
+    pub fn max_scroll(&self) -> usize {
+        self.content_height.saturating_sub(self.page_height)
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll = (self.scroll + rows).min(self.max_scroll());
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.page_height.saturating_sub(1).max(1));
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.page_height.saturating_sub(1).max(1));
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = self.max_scroll();
+    }
+
+    // Position shown in the corner of the reader, like a pager does.
+    pub fn position(&self) -> String {
+        match self.max_scroll() {
+            0 => "All".to_string(),
+            _ if self.scroll == 0 => "Top".to_string(),
+            max if self.scroll >= max => "Bot".to_string(),
+            max => format!("{}%", self.scroll * 100 / max),
+        }
+    }
+}
+
+// Lines wrapped to a width, with the rows and columns the link references ended up at.
+#[derive(Debug, Default)]
+pub struct Layout {
+    pub width: u16,
+    pub lines: Vec<Line<'static>>,
+    // Row, column, width and link index of every piece of a reference,
+    // a reference broken over two rows has two pieces.
+    pub references: Vec<(usize, u16, u16, usize)>,
+}
+
+// A row of a wrapped line and the column of the line it starts at.
+struct Row {
+    start: Option<usize>,
+    width: usize,
+    spans: Vec<Span<'static>>,
+}
+
+impl Row {
+    fn new() -> Self {
+        Self {
+            start: None,
+            width: 0,
+            spans: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, column: usize, symbol: &str, style: Style, width: usize) {
+        self.start.get_or_insert(column);
+        self.width += width;
+        match self.spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push_str(symbol),
+            _ => self.spans.push(Span::styled(symbol.to_string(), style)),
+        }
+    }
+
+    // Drops the whitespace a row ends with where a line is broken.
+    fn trim_end(mut self) -> Self {
+        while let Some(span) = self.spans.last_mut() {
+            let trimmed = span.content.trim_end().len();
+            self.width -= span.content[trimmed..].width();
+            span.content.to_mut().truncate(trimmed);
+            if !span.content.is_empty() {
+                break;
+            }
+            self.spans.pop();
+        }
+        self
+    }
+}
+
+impl Layout {
+    // Wraps the lines at whitespace, words longer than a row are broken up.
+    // Whitespace at the start of a line is kept, whitespace where a line is broken is dropped.
+    pub fn wrap(lines: &[Line<'static>], references: &[LinkReference], width: u16) -> Self {
+        let max_width = usize::from(width.max(1));
+        let mut layout = Self {
+            width,
+            ..Self::default()
+        };
+
+        for (index, line) in lines.iter().enumerate() {
+            let graphemes: Vec<(&str, Style, usize)> = line
+                .styled_graphemes(Style::default())
+                .map(|grapheme| (grapheme.symbol, grapheme.style, grapheme.symbol.width()))
+                .collect();
+            let mut rows = Vec::new();
+            let mut row = Row::new();
+            let mut column = 0;
+
+            for word in graphemes.chunk_by(|a, b| is_whitespace(a.0) == is_whitespace(b.0)) {
+                let word_width: usize = word.iter().map(|&(_, _, width)| width).sum();
+                if is_whitespace(word[0].0) {
+                    let continued = !rows.is_empty() && row.spans.is_empty();
+                    if row.width + word_width > max_width {
+                        rows.push(std::mem::replace(&mut row, Row::new()).trim_end());
+                    } else if !continued {
+                        for &(symbol, style, width) in word {
+                            row.push(column, symbol, style, width);
+                            column += width;
+                        }
+                        continue;
+                    }
+                    column += word_width;
+                    continue;
+                }
+
+                if row.width + word_width > max_width && row.width > 0 {
+                    rows.push(std::mem::replace(&mut row, Row::new()).trim_end());
+                }
+                for &(symbol, style, width) in word {
+                    if row.width + width > max_width && row.width > 0 {
+                        rows.push(std::mem::replace(&mut row, Row::new()).trim_end());
+                    }
+                    row.push(column, symbol, style, width);
+                    column += width;
+                }
+            }
+            if row.width > 0 || rows.is_empty() {
+                rows.push(row);
+            }
+
+            let first_row = layout.lines.len();
+            for reference in references
+                .iter()
+                .filter(|reference| reference.line == index)
+            {
+                let end = reference.column + reference.width;
+                for (offset, row) in rows.iter().enumerate() {
+                    let Some(start) = row.start else {
+                        continue;
+                    };
+                    let (from, to) = (reference.column.max(start), end.min(start + row.width));
+                    if from < to {
+                        layout.references.push((
+                            first_row + offset,
+                            (from - start) as u16,
+                            (to - from) as u16,
+                            reference.link,
+                        ));
+                    }
+                }
+            }
+            layout
+                .lines
+                .extend(rows.into_iter().map(|row| Line::from(row.spans)));
+        }
+        layout
+    }
+}
+
+fn is_whitespace(symbol: &str) -> bool {
+    symbol.chars().all(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(line: usize, column: usize, width: usize, link: usize) -> LinkReference {
+        LinkReference {
+            line,
+            column,
+            width,
+            link,
+        }
+    }
+
+    fn text(layout: &Layout) -> Vec<String> {
+        layout.lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn wraps_at_whitespace_and_keeps_indentation() {
+        let layout = Layout::wrap(&[Line::from("  one two three")], &[], 9);
+        assert_eq!(text(&layout), ["  one two", "three"]);
+    }
+
+    #[test]
+    fn moves_references_with_their_word() {
+        let layout = Layout::wrap(&[Line::from("abc [12]")], &[reference(0, 4, 4, 11)], 6);
+        assert_eq!(text(&layout), ["abc", "[12]"]);
+        assert_eq!(layout.references, [(1, 0, 4, 11)]);
+    }
+
+    #[test]
+    fn splits_references_broken_over_rows() {
+        let layout = Layout::wrap(&[Line::from("abc [12]")], &[reference(0, 4, 4, 11)], 3);
+        assert_eq!(text(&layout), ["abc", "[12", "]"]);
+        assert_eq!(layout.references, [(1, 0, 3, 11), (2, 0, 1, 11)]);
+    }
+
+    #[test]
+    fn keeps_adjacent_references_apart() {
+        let lines = [Line::from(""), Line::from("x [1][2]")];
+        let references = [reference(1, 2, 3, 0), reference(1, 5, 3, 1)];
+        let layout = Layout::wrap(&lines, &references, 20);
+        assert_eq!(layout.references, [(1, 2, 3, 0), (1, 5, 3, 1)]);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::subscription::Subscription;
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc, Weekday};
+use rustc_hash::FxHashMap;
+
+// Hints of a feed on how often it should be fetched,
+// taken from RSS `<ttl>`, `<skipHours>`, `<skipDays>` and `sy:updatePeriod`.
+#[derive(Clone, Debug, Default)]
+pub struct ScheduleHints {
+    pub min_interval: Option<Duration>,
+    // Hours in GMT during which the feed should not be fetched.
+    pub skip_hours: Vec<u32>,
+    pub skip_days: Vec<Weekday>,
+}
+
+impl ScheduleHints {
+    pub fn from_rss(channel: &rss::Channel) -> Self {
+        let ttl = channel
+            .ttl()
+            .and_then(|ttl| ttl.trim().parse::<i64>().ok())
+            .map(Duration::minutes);
+
+        let update_period = channel.syndication_ext().map(|sy| {
+            use rss::extension::syndication::UpdatePeriod;
+            let period = match sy.period() {
+                UpdatePeriod::Hourly => Duration::hours(1),
+                UpdatePeriod::Daily => Duration::days(1),
+                UpdatePeriod::Weekly => Duration::weeks(1),
+                UpdatePeriod::Monthly => Duration::days(30),
+                UpdatePeriod::Yearly => Duration::days(365),
+            };
+            period / sy.frequency().max(1) as i32
+        });
+
+        Self {
+            min_interval: ttl.max(update_period),
+            skip_hours: channel
+                .skip_hours()
+                .iter()
+                .filter_map(|hour| hour.trim().parse().ok())
+                // `24` is sometimes used for midnight
+                .map(|hour: u32| hour % 24)
+                .collect(),
+            skip_days: channel
+                .skip_days()
+                .iter()
+                .filter_map(|day| day.trim().parse().ok())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FeedSchedule {
+    next_fetch: DateTime<Utc>,
+    in_flight: bool,
+    interval: Option<Duration>,
+    hints: ScheduleHints,
+}
+
+// Decides when each subscription is fetched again.
+#[derive(Debug)]
+pub struct Scheduler {
+    default_interval: Duration,
+    feeds: FxHashMap<String, FeedSchedule>,
+}
+
+impl Scheduler {
+    pub fn new(default_interval: std::time::Duration) -> Self {
+        Self {
+            default_interval: Duration::from_std(default_interval).unwrap_or(Duration::minutes(30)),
+            feeds: FxHashMap::default(),
+        }
+    }
+
+    // Adds a subscription that is due immediately.
+    pub fn add(&mut self, subscription: &Subscription) {
+        self.feeds.insert(
+            subscription.url.clone(),
+            FeedSchedule {
+                next_fetch: Utc::now(),
+                in_flight: false,
+                interval: subscription
+                    .refresh_interval
+                    .map(|minutes| Duration::minutes(minutes.max(1) as i64)),
+                hints: ScheduleHints::default(),
+            },
+        );
+    }
+
+    // Returns all feeds that should be fetched now and marks them as being fetched.
+    pub fn due(&mut self) -> Vec<String> {
+        let now = Utc::now();
+        self.feeds
+            .iter_mut()
+            .filter(|(_, schedule)| !schedule.in_flight && schedule.next_fetch <= now)
+            .map(|(url, schedule)| {
+                schedule.in_flight = true;
+                url.clone()
+            })
+            .collect()
+    }
+
+    // Fetches the feed at the next call to `due`, ignoring its hints.
+    pub fn request(&mut self, url: &str) {
+        if let Some(schedule) = self.feeds.get_mut(url) {
+            schedule.next_fetch = Utc::now();
+        }
+    }
+
+    pub fn request_all(&mut self) {
+        let now = Utc::now();
+        for schedule in self.feeds.values_mut() {
+            schedule.next_fetch = now;
+        }
+    }
+
+    // Plans the next fetch of a feed after it has been fetched.
+    // `hints` is `None` if the fetch failed, the previous hints are kept then.
+    pub fn fetched(&mut self, url: &str, hints: Option<ScheduleHints>) {
+        let default_interval = self.default_interval;
+        let Some(schedule) = self.feeds.get_mut(url) else {
+            return;
+        };
+        if let Some(hints) = hints {
+            schedule.hints = hints;
+        }
+
+        let interval = schedule.interval.unwrap_or(default_interval);
+        let interval = match schedule.hints.min_interval {
+            Some(min_interval) => interval.max(min_interval),
+            None => interval,
+        };
+
+        schedule.in_flight = false;
+        schedule.next_fetch = skip_blocked_hours(Utc::now() + interval, &schedule.hints);
+    }
+}
+
+// Moves `time` forward to the first hour not excluded by `skipHours` or `skipDays`.
+fn skip_blocked_hours(next_fetch: DateTime<Utc>, hints: &ScheduleHints) -> DateTime<Utc> {
+    let mut time = next_fetch;
+    // A week has all combinations of days and hours, a feed blocking everything is ignored
+    for _ in 0..(24 * 7) {
+        let blocked =
+            hints.skip_hours.contains(&time.hour()) || hints.skip_days.contains(&time.weekday());
+        if !blocked {
+            return time;
+        }
+        time = time.duration_trunc(Duration::hours(1)).unwrap_or(time) + Duration::hours(1);
+    }
+    next_fetch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().into()
+    }
+
+    fn channel(elements: &str) -> rss::Channel {
+        let xml = format!(
+            r#"<rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+               <channel><title>Feed</title><link>https://example.com</link>
+               <description></description>{}</channel></rss>"#,
+            elements
+        );
+        rss::Channel::read_from(xml.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn reads_hints() {
+        let hints = ScheduleHints::from_rss(&channel(
+            "<ttl>120</ttl>
+             <skipHours><hour>24</hour><hour>5</hour><hour>x</hour></skipHours>
+             <skipDays><day>Saturday</day><day>Someday</day></skipDays>
+             <sy:updatePeriod>hourly</sy:updatePeriod><sy:updateFrequency>2</sy:updateFrequency>",
+        ));
+        assert_eq!(hints.min_interval, Some(Duration::hours(2)));
+        assert_eq!(hints.skip_hours, [0, 5]);
+        assert_eq!(hints.skip_days, [Weekday::Sat]);
+
+        let hints = ScheduleHints::from_rss(&channel(
+            "<ttl>10</ttl>
+             <sy:updatePeriod>daily</sy:updatePeriod><sy:updateFrequency>4</sy:updateFrequency>",
+        ));
+        assert_eq!(hints.min_interval, Some(Duration::hours(6)));
+    }
+
+    #[test]
+    fn skips_hours_across_midnight() {
+        let hints = ScheduleHints {
+            skip_hours: vec![22, 23, 0, 1],
+            ..Default::default()
+        };
+        assert_eq!(
+            skip_blocked_hours(time("2024-03-01T22:30:00Z"), &hints),
+            time("2024-03-02T02:00:00Z")
+        );
+        assert_eq!(
+            skip_blocked_hours(time("2024-03-01T21:59:00Z"), &hints),
+            time("2024-03-01T21:59:00Z")
+        );
+    }
+
+    #[test]
+    fn skips_days_into_next_week() {
+        let hints = ScheduleHints {
+            skip_hours: vec![0],
+            skip_days: vec![Weekday::Sat, Weekday::Sun],
+            ..Default::default()
+        };
+        // 2024-03-02 is a Saturday
+        assert_eq!(
+            skip_blocked_hours(time("2024-03-02T10:15:00Z"), &hints),
+            time("2024-03-04T01:00:00Z")
+        );
+    }
+
+    #[test]
+    fn ignores_blocking_everything() {
+        let hints = ScheduleHints {
+            skip_hours: (0..24).collect(),
+            ..Default::default()
+        };
+        let next_fetch = time("2024-03-01T12:34:00Z");
+        assert_eq!(skip_blocked_hours(next_fetch, &hints), next_fetch);
+
+        let hints = ScheduleHints {
+            skip_days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+                Weekday::Sat,
+                Weekday::Sun,
+            ],
+            ..Default::default()
+        };
+        assert_eq!(skip_blocked_hours(next_fetch, &hints), next_fetch);
+    }
+
+    #[test]
+    fn uses_ttl_as_lower_bound() {
+        let mut subscription = Subscription::new("https://example.com/feed");
+        let mut scheduler = Scheduler::new(std::time::Duration::from_secs(30 * 60));
+        let hints = || ScheduleHints {
+            min_interval: Some(Duration::hours(2)),
+            ..Default::default()
+        };
+        let next_fetch = |scheduler: &Scheduler| scheduler.feeds[&subscription.url].next_fetch;
+
+        scheduler.add(&subscription);
+        let before = Utc::now();
+        scheduler.fetched(&subscription.url, Some(hints()));
+        let wait = next_fetch(&scheduler) - before;
+        assert!(wait >= Duration::hours(2) && wait < Duration::hours(2) + Duration::minutes(1));
+
+        // Failed fetches keep the hints
+        scheduler.fetched(&subscription.url, None);
+        assert!(next_fetch(&scheduler) - before >= Duration::hours(2));
+
+        // A longer interval of the subscription wins
+        subscription.refresh_interval = Some(180);
+        scheduler.add(&subscription);
+        scheduler.fetched(&subscription.url, Some(hints()));
+        assert!(next_fetch(&scheduler) - before >= Duration::hours(3));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::article::Article;
+use crate::html::Document;
+use rustc_hash::FxHashMap;
+use std::ops::Range;
+
+// Incremental search over the articles in the list.
+#[derive(Debug, Default)]
+pub struct Search {
+    pub query: String,
+    // Whether keys are typed into the prompt.
+    pub editing: bool,
+    // Selection before the search started, restored when it is cancelled.
+    pub previous_selection: Option<Article>,
+    // Descriptions without markup, converted once per article.
+    descriptions: FxHashMap<(String, String), String>,
+}
+
+impl Search {
+    pub fn new(previous_selection: Option<Article>) -> Self {
+        Self {
+            editing: true,
+            previous_selection,
+            ..Self::default()
+        }
+    }
+
+    // Whether the title, source, an author or the description contains the query, ignoring case.
+    pub fn matches(&mut self, article: &Article) -> bool {
+        if self.query.is_empty() {
+            return true;
+        }
+        let fields = [article.title.as_str(), article.feed_title.as_str()];
+        if fields
+            .into_iter()
+            .chain(article.authors.iter().map(String::as_str))
+            .any(|field| !find_matches(field, &self.query).is_empty())
+        {
+            return true;
+        }
+
+        let description = self
+            .descriptions
+            .entry((article.feed_id.clone(), article.id.clone()))
+            .or_insert_with(|| Document::plain_text(article.description()));
+        !find_matches(description, &self.query).is_empty()
+    }
+}
+
+// Byte ranges of all occurrences of `query` in `text`, ignoring case.
+pub fn find_matches(text: &str, query: &str) -> Vec<Range<usize>> {
+    let query: Vec<char> = query.chars().collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + query.len() <= chars.len() {
+        let window = &chars[start..start + query.len()];
+        if window.iter().zip(&query).all(|(&(_, c), &q)| same(c, q)) {
+            let end = chars
+                .get(start + query.len())
+                .map_or(text.len(), |(index, _)| *index);
+            matches.push(chars[start].0..end);
+            start += query.len();
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched<'a>(text: &'a str, query: &str) -> Vec<&'a str> {
+        find_matches(text, query)
+            .into_iter()
+            .map(|range| {
+                assert!(text.is_char_boundary(range.start) && text.is_char_boundary(range.end));
+                &text[range]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ignores_case_of_non_ascii_text() {
+        assert_eq!(find_matches("Über über", "üBER"), [0..5, 6..11]);
+        assert_eq!(matched("Grüße aus GRÜSSE", "grüße"), ["Grüße"]);
+        assert_eq!(matched("STRAẞE und straße", "straße"), ["STRAẞE", "straße"]);
+        assert_eq!(matched("日本語のニュース", "ニュース"), ["ニュース"]);
+        assert_eq!(matched("Ærø", "ø"), ["ø"]);
+    }
+
+    #[test]
+    fn finds_adjacent_matches_without_overlap() {
+        assert_eq!(find_matches("abab", "ab"), [0..2, 2..4]);
+        assert_eq!(matched("aaa", "aa"), ["aa"]);
+        assert_eq!(matched("ßßß", "ßß"), ["ßß"]);
+        assert_eq!(matched("ÖöÖö", "öÖ"), ["Öö", "Öö"]);
+    }
+
+    #[test]
+    fn matches_nothing_for_empty_or_longer_queries() {
+        assert!(find_matches("title", "").is_empty());
+        assert!(find_matches("", "title").is_empty());
+        assert!(find_matches("Ü", "Üb").is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::subscription::Subscription;
+use std::collections::BTreeMap;
+
+// Articles shown in the list, chosen through the sidebar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    // The subscription with the given URL.
+    Feed(String),
+    // All subscriptions in the folder with the given path and its subfolders.
+    Folder(String),
+    // Starred articles of all feeds, including those no longer subscribed.
+    Starred,
+}
+
+impl Filter {
+    pub fn includes(&self, subscription: &Subscription) -> bool {
+        match self {
+            Filter::Feed(url) => subscription.url == *url,
+            Filter::Folder(path) => subscription.in_folder(path),
+            // Starred articles are chosen by their id
+            Filter::Starred => false,
+        }
+    }
+}
+
+// A line of the sidebar, "All items" if it has no filter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SidebarEntry {
+    pub filter: Option<Filter>,
+    // Nesting level, entries inside a folder are one deeper than the folder.
+    pub depth: usize,
+}
+
+// Folder tree of the subscriptions, a subscription in several folders is in each of them.
+#[derive(Default)]
+struct Folder<'a> {
+    folders: BTreeMap<&'a str, Folder<'a>>,
+    feeds: Vec<&'a Subscription>,
+}
+
+impl<'a> Folder<'a> {
+    fn push_entries(&self, path: &str, depth: usize, entries: &mut Vec<SidebarEntry>) {
+        for (name, folder) in &self.folders {
+            let path = match path {
+                "" => name.to_string(),
+                parent => format!("{}/{}", parent, name),
+            };
+            entries.push(SidebarEntry {
+                filter: Some(Filter::Folder(path.clone())),
+                depth,
+            });
+            folder.push_entries(&path, depth + 1, entries);
+        }
+        entries.extend(self.feeds.iter().map(|subscription| SidebarEntry {
+            filter: Some(Filter::Feed(subscription.url.clone())),
+            depth,
+        }));
+    }
+}
+
+// "All items" and "Starred" followed by the folders with their feeds and then the feeds outside of folders.
+pub fn entries(subscriptions: &[Subscription]) -> Vec<SidebarEntry> {
+    let mut root = Folder::default();
+    for subscription in subscriptions {
+        if subscription.folders.is_empty() {
+            root.feeds.push(subscription);
+        }
+        for path in &subscription.folders {
+            let folder = path
+                .split('/')
+                .filter(|name| !name.is_empty())
+                .fold(&mut root, |folder, name| {
+                    folder.folders.entry(name).or_default()
+                });
+            folder.feeds.push(subscription);
+        }
+    }
+
+    let mut entries = vec![
+        SidebarEntry {
+            filter: None,
+            depth: 0,
+        },
+        SidebarEntry {
+            filter: Some(Filter::Starred),
+            depth: 0,
+        },
+    ];
+    root.push_entries("", 0, &mut entries);
+    entries
+}
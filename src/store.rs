@@ -0,0 +1,536 @@
+use crate::article::{Article, ArticleSet};
+use chrono::DateTime;
+use rusqlite::{params, Connection, Row};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Version of the schema, stored as `user_version` of the database.
+const SCHEMA_VERSION: i64 = 2;
+
+// Days after which removed articles may come back if their feed no longer lists them.
+const REMOVED_DAYS: i64 = 30;
+
+const SCHEMA: &str = "
+    CREATE TABLE articles (
+        feed_id TEXT NOT NULL,
+        id TEXT NOT NULL,
+        feed_title TEXT NOT NULL,
+        title TEXT NOT NULL,
+        link TEXT,
+        authors TEXT NOT NULL,
+        categories TEXT NOT NULL,
+        summary TEXT,
+        content TEXT,
+        enclosures TEXT NOT NULL,
+        images TEXT NOT NULL,
+        published TEXT,
+        updated TEXT,
+        -- Unix time of the article's date, used for sorting and retention
+        date INTEGER,
+        first_seen INTEGER NOT NULL,
+        read INTEGER NOT NULL DEFAULT 0,
+        starred INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (feed_id, id)
+    );
+    -- Ids of read articles from `read.json`, applied to articles when they are first stored
+    CREATE TABLE legacy_read (id TEXT PRIMARY KEY);
+";
+
+// Articles removed by the retention, so fetching them again doesn't bring them back.
+const REMOVED_ARTICLES: &str = "
+    CREATE TABLE removed_articles (
+        feed_id TEXT NOT NULL,
+        id TEXT NOT NULL,
+        -- Unix time of when the feed last listed the article
+        last_seen INTEGER NOT NULL,
+        PRIMARY KEY (feed_id, id)
+    );
+";
+
+// How many articles `gc` keeps, starred articles are always kept.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retention {
+    // Articles older than this many days are removed.
+    pub days: Option<u64>,
+    // Only the newest articles of every feed are kept.
+    pub per_feed: Option<usize>,
+}
+
+// Outcome of `Store::upsert`.
+#[derive(Debug, Default)]
+pub struct Upserted {
+    // Read articles, e.g. because their id was in `read.json`.
+    pub read: ArticleSet,
+    // Articles that weren't stored because the retention removed them before.
+    pub removed: ArticleSet,
+}
+
+// Database of all articles ever fetched with their read and starred state.
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    // Opens or creates the database, ids from an old `read.json` are taken over once.
+    pub fn open(path: &Path, read_articles_file: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(path)?;
+        let store = Self { connection };
+        store.migrate()?;
+        store.import_read_articles(read_articles_file)?;
+        Ok(store)
+    }
+
+    fn migrate(&self) -> anyhow::Result<()> {
+        let version: i64 = self
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        match version {
+            0 => {
+                self.connection.execute_batch(SCHEMA)?;
+                self.connection.execute_batch(REMOVED_ARTICLES)?;
+            }
+            1 => self.connection.execute_batch(REMOVED_ARTICLES)?,
+            SCHEMA_VERSION => return Ok(()),
+            version => anyhow::bail!(
+                "The article database has version {}, which is newer than this version of ta-rss",
+                version
+            ),
+        }
+        self.connection
+            .pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(())
+    }
+
+    // Moves the ids of `read.json` into the database and keeps the file as `read.json.bak`.
+    fn import_read_articles(&self, file: &Path) -> anyhow::Result<()> {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            return Ok(());
+        };
+        let ids: Vec<String> = serde_json::from_str(&content).unwrap_or_default();
+        let transaction = self.connection.unchecked_transaction()?;
+        for id in &ids {
+            transaction.execute(
+                "INSERT OR IGNORE INTO legacy_read (id) VALUES (?1)",
+                params![id],
+            )?;
+            transaction.execute("UPDATE articles SET read = 1 WHERE id = ?1", params![id])?;
+        }
+        transaction.commit()?;
+        std::fs::rename(file, file.with_extension("json.bak"))?;
+        Ok(())
+    }
+
+    pub fn load(&self) -> anyhow::Result<Vec<Article>> {
+        let mut statement = self.connection.prepare(
+            "SELECT feed_id, id, feed_title, title, link, authors, categories, summary, content,
+                    enclosures, images, published, updated
+             FROM articles",
+        )?;
+        let articles = statement
+            .query_map([], stored_article)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(articles)
+    }
+
+    pub fn is_empty(&self) -> anyhow::Result<bool> {
+        let count: i64 = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+
+    // Read articles, those of `read.json` become read when they are stored.
+    pub fn read_articles(&self) -> anyhow::Result<ArticleSet> {
+        self.article_set("SELECT feed_id, id FROM articles WHERE read = 1")
+    }
+
+    pub fn starred_articles(&self) -> anyhow::Result<ArticleSet> {
+        self.article_set("SELECT feed_id, id FROM articles WHERE starred = 1")
+    }
+
+    fn article_set(&self, query: &str) -> anyhow::Result<ArticleSet> {
+        let mut statement = self.connection.prepare(query)?;
+        let articles = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(articles)
+    }
+
+    // Inserts new articles and updates the content of known ones, keeping their state.
+    // Articles removed by the retention are left out.
+    pub fn upsert(&self, articles: &[Article]) -> anyhow::Result<Upserted> {
+        let mut upserted = Upserted::default();
+        let transaction = self.connection.unchecked_transaction()?;
+        {
+            let mut removed = transaction.prepare_cached(
+                "UPDATE removed_articles SET last_seen = ?3 WHERE feed_id = ?1 AND id = ?2",
+            )?;
+            let mut statement = transaction.prepare_cached(
+                "INSERT INTO articles (feed_id, id, feed_title, title, link, authors, categories,
+                     summary, content, enclosures, images, published, updated, date, first_seen, read)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+                     EXISTS (SELECT 1 FROM legacy_read WHERE id = ?2))
+                 ON CONFLICT (feed_id, id) DO UPDATE SET
+                     feed_title = excluded.feed_title, title = excluded.title,
+                     link = excluded.link, authors = excluded.authors,
+                     categories = excluded.categories, summary = excluded.summary,
+                     content = excluded.content, enclosures = excluded.enclosures,
+                     images = excluded.images, published = excluded.published,
+                     updated = excluded.updated, date = excluded.date
+                 RETURNING read",
+            )?;
+            let now = unix_time(SystemTime::now());
+            for article in articles {
+                if removed.execute(params![article.feed_id, article.id, now])? > 0 {
+                    upserted.removed.insert(article);
+                    continue;
+                }
+                let is_read: bool = statement.query_row(
+                    params![
+                        article.feed_id,
+                        article.id,
+                        article.feed_title,
+                        article.title,
+                        article.link,
+                        serde_json::to_string(&article.authors)?,
+                        serde_json::to_string(&article.categories)?,
+                        article.summary,
+                        article.content,
+                        serde_json::to_string(&article.enclosures)?,
+                        serde_json::to_string(&article.images)?,
+                        article.published.map(|date| date.to_rfc3339()),
+                        article.updated.map(|date| date.to_rfc3339()),
+                        article.date().map(|date| date.timestamp()),
+                        now,
+                    ],
+                    |row| row.get(0),
+                )?;
+                if is_read {
+                    upserted.read.insert(article);
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(upserted)
+    }
+
+    pub fn set_read(&self, article: &Article, read: bool) -> anyhow::Result<()> {
+        self.connection.execute(
+            "UPDATE articles SET read = ?3 WHERE feed_id = ?1 AND id = ?2",
+            params![article.feed_id, article.id, read],
+        )?;
+        if !read {
+            self.connection
+                .execute("DELETE FROM legacy_read WHERE id = ?1", params![article.id])?;
+        }
+        Ok(())
+    }
+
+    pub fn set_starred(&self, article: &Article, starred: bool) -> anyhow::Result<()> {
+        self.connection.execute(
+            "UPDATE articles SET starred = ?3 WHERE feed_id = ?1 AND id = ?2",
+            params![article.feed_id, article.id, starred],
+        )?;
+        Ok(())
+    }
+
+    // Starred articles, newest first.
+    pub fn load_starred(&self) -> anyhow::Result<Vec<Article>> {
+        let mut statement = self.connection.prepare(
+            "SELECT feed_id, id, feed_title, title, link, authors, categories, summary, content,
+                    enclosures, images, published, updated
+             FROM articles WHERE starred = 1
+             ORDER BY COALESCE(date, first_seen) DESC",
+        )?;
+        let articles = statement
+            .query_map([], stored_article)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(articles)
+    }
+
+    // Removes articles of feeds that aren't subscribed anymore and those outside of the retention,
+    // starred articles are kept. Returns the number of removed articles.
+    // Articles removed by the retention aren't stored again while their feed still lists them.
+    pub fn gc(&self, subscribed: &[&str], retention: Retention) -> anyhow::Result<usize> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let mut removed = 0;
+
+        let feeds: Vec<String> = {
+            let mut statement = transaction.prepare(
+                "SELECT feed_id FROM articles UNION SELECT feed_id FROM removed_articles",
+            )?;
+            let feeds = statement
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            feeds
+        };
+        for feed in feeds
+            .iter()
+            .filter(|feed| !subscribed.contains(&feed.as_str()))
+        {
+            removed += transaction.execute(
+                "DELETE FROM articles WHERE feed_id = ?1 AND starred = 0",
+                params![feed],
+            )?;
+            transaction.execute(
+                "DELETE FROM removed_articles WHERE feed_id = ?1",
+                params![feed],
+            )?;
+        }
+
+        let now = unix_time(SystemTime::now());
+        // Articles without a date count from when they were first seen
+        // A limit too large to compute keeps everything
+        let limit = retention.days.and_then(|days| {
+            let seconds = i64::try_from(days).ok()?.checked_mul(24 * 60 * 60)?;
+            now.checked_sub(seconds)
+        });
+        if let Some(limit) = limit {
+            removed += remove_articles(
+                &transaction,
+                "starred = 0 AND COALESCE(date, first_seen) < ?1",
+                limit,
+                now,
+            )?;
+        }
+        if let Some(per_feed) = retention.per_feed {
+            removed += remove_articles(
+                &transaction,
+                "rowid IN (
+                     SELECT rowid FROM (
+                         SELECT rowid, ROW_NUMBER() OVER (
+                             PARTITION BY feed_id ORDER BY COALESCE(date, first_seen) DESC
+                         ) AS position
+                         FROM articles WHERE starred = 0
+                     ) WHERE position > ?1
+                 )",
+                i64::try_from(per_feed).unwrap_or(i64::MAX),
+                now,
+            )?;
+        }
+        transaction.execute(
+            "DELETE FROM removed_articles WHERE last_seen < ?1",
+            params![now - REMOVED_DAYS * 24 * 60 * 60],
+        )?;
+
+        // Read ids from `read.json` are no longer needed once their article is stored
+        transaction.execute(
+            "DELETE FROM legacy_read WHERE id IN (SELECT id FROM articles)",
+            [],
+        )?;
+        transaction.commit()?;
+        Ok(removed)
+    }
+
+    // Shrinks the file after articles were removed.
+    pub fn vacuum(&self) -> anyhow::Result<()> {
+        self.connection.execute("VACUUM", [])?;
+        Ok(())
+    }
+}
+
+// Deletes the articles matching the condition, which gets `limit` as `?1`, and remembers them
+// as removed.
+fn remove_articles(
+    connection: &Connection,
+    condition: &str,
+    limit: i64,
+    now: i64,
+) -> rusqlite::Result<usize> {
+    connection.execute(
+        &format!(
+            "INSERT OR REPLACE INTO removed_articles (feed_id, id, last_seen)
+             SELECT feed_id, id, ?2 FROM articles WHERE {}",
+            condition
+        ),
+        params![limit, now],
+    )?;
+    connection.execute(
+        &format!("DELETE FROM articles WHERE {}", condition),
+        params![limit],
+    )
+}
+
+fn stored_article(row: &Row) -> rusqlite::Result<Article> {
+    let json = |index: usize| -> rusqlite::Result<String> { row.get(index) };
+    let date = |index: usize| -> rusqlite::Result<_> {
+        Ok(row
+            .get::<_, Option<String>>(index)?
+            .and_then(|date| DateTime::parse_from_rfc3339(&date).ok()))
+    };
+    Ok(Article {
+        feed_id: row.get(0)?,
+        id: row.get(1)?,
+        feed_title: row.get(2)?,
+        title: row.get(3)?,
+        link: row.get(4)?,
+        authors: serde_json::from_str(&json(5)?).unwrap_or_default(),
+        categories: serde_json::from_str(&json(6)?).unwrap_or_default(),
+        summary: row.get(7)?,
+        content: row.get(8)?,
+        enclosures: serde_json::from_str(&json(9)?).unwrap_or_default(),
+        images: serde_json::from_str(&json(10)?).unwrap_or_default(),
+        published: date(11)?,
+        updated: date(12)?,
+    })
+}
+
+fn unix_time(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn store() -> Store {
+        Store::open(Path::new(":memory:"), Path::new("")).unwrap()
+    }
+
+    fn article(feed_id: &str, id: &str, days_ago: Option<i64>) -> Article {
+        Article {
+            feed_id: feed_id.to_string(),
+            id: id.to_string(),
+            published: days_ago.map(|days| (Utc::now() - Duration::days(days)).fixed_offset()),
+            ..Default::default()
+        }
+    }
+
+    fn ids(store: &Store) -> Vec<String> {
+        let mut ids: Vec<String> = store
+            .load()
+            .unwrap()
+            .into_iter()
+            .map(|article| format!("{}/{}", article.feed_id, article.id))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn keeps_newest_articles_per_feed() {
+        let store = store();
+        store
+            .upsert(&[
+                article("a", "1", Some(1)),
+                article("a", "2", Some(2)),
+                article("a", "3", Some(3)),
+                article("a", "4", Some(4)),
+                article("b", "1", Some(5)),
+            ])
+            .unwrap();
+        store.set_starred(&article("a", "4", None), true).unwrap();
+
+        let retention = Retention {
+            per_feed: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(store.gc(&["a", "b"], retention).unwrap(), 1);
+        assert_eq!(ids(&store), ["a/1", "a/2", "a/4", "b/1"]);
+
+        let retention = Retention {
+            per_feed: Some(usize::MAX),
+            ..Default::default()
+        };
+        assert_eq!(store.gc(&["a", "b"], retention).unwrap(), 0);
+    }
+
+    #[test]
+    fn removes_old_articles() {
+        let store = store();
+        store
+            .upsert(&[
+                article("a", "new", Some(1)),
+                article("a", "old", Some(30)),
+                article("a", "starred", Some(30)),
+                // Counts from when it was first seen, which is now
+                article("a", "undated", None),
+            ])
+            .unwrap();
+        store
+            .set_starred(&article("a", "starred", None), true)
+            .unwrap();
+
+        let retention = Retention {
+            days: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(store.gc(&["a"], retention).unwrap(), 1);
+        assert_eq!(ids(&store), ["a/new", "a/starred", "a/undated"]);
+
+        let retention = Retention {
+            days: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert_eq!(store.gc(&["a"], retention).unwrap(), 0);
+    }
+
+    #[test]
+    fn removes_unsubscribed_feeds() {
+        let store = store();
+        store
+            .upsert(&[
+                article("a", "1", Some(1)),
+                article("b", "1", Some(1)),
+                article("b", "2", Some(1)),
+            ])
+            .unwrap();
+        store.set_starred(&article("b", "2", None), true).unwrap();
+
+        assert_eq!(store.gc(&["a"], Retention::default()).unwrap(), 1);
+        assert_eq!(ids(&store), ["a/1", "b/2"]);
+    }
+
+    #[test]
+    fn keeps_removed_articles_out() {
+        let store = store();
+        let articles = [article("a", "new", Some(1)), article("a", "old", Some(2))];
+        store.upsert(&articles).unwrap();
+        store.set_read(&articles[0], true).unwrap();
+        let retention = Retention {
+            per_feed: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(store.gc(&["a"], retention).unwrap(), 1);
+
+        let upserted = store.upsert(&articles).unwrap();
+        assert!(upserted.removed.contains(&articles[1]));
+        assert!(!upserted.removed.contains(&articles[0]));
+        assert!(upserted.read.contains(&articles[0]));
+        assert_eq!(ids(&store), ["a/new"]);
+        assert_eq!(store.gc(&["a"], retention).unwrap(), 0);
+
+        // Unsubscribing forgets them
+        store.gc(&[], retention).unwrap();
+        assert!(!store
+            .upsert(&articles)
+            .unwrap()
+            .removed
+            .contains(&articles[1]));
+    }
+
+    #[test]
+    fn keys_state_by_feed_and_id() {
+        let store = store();
+        store
+            .upsert(&[article("a", "1", None), article("b", "1", None)])
+            .unwrap();
+        store.set_read(&article("a", "1", None), true).unwrap();
+        store.set_starred(&article("b", "1", None), true).unwrap();
+
+        let read = store.read_articles().unwrap();
+        assert!(read.contains(&article("a", "1", None)));
+        assert!(!read.contains(&article("b", "1", None)));
+        let starred = store.starred_articles().unwrap();
+        assert!(!starred.contains(&article("a", "1", None)));
+        assert!(starred.contains(&article("b", "1", None)));
+    }
+}
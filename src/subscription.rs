@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+// A subscribed feed as stored in `feeds.json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "StoredSubscription")]
+pub struct Subscription {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    // Title given by the user, shown instead of the one of the feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_title: Option<String>,
+    // Folder paths with nested folders separated by `/`, e.g. `Work/News`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folders: Vec<String>,
+    // Minutes between two fetches, overriding the `refresh_interval` of the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_interval: Option<u64>,
+}
+
+impl Subscription {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            title: None,
+            custom_title: None,
+            folders: Vec::new(),
+            refresh_interval: None,
+        }
+    }
+
+    // Title to show for the subscription, the URL if no title is known.
+    pub fn display_name(&self) -> &str {
+        self.custom_title
+            .as_deref()
+            .or(self.title.as_deref())
+            .unwrap_or(&self.url)
+    }
+
+    // Whether the subscription is in the folder or one of its subfolders.
+    pub fn in_folder(&self, path: &str) -> bool {
+        self.folders.iter().any(|folder| {
+            folder == path
+                || folder
+                    .strip_prefix(path)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+// Folder path with surrounding whitespace and empty segments removed, `None` if nothing is left.
+pub fn normalize_folder(path: &str) -> Option<String> {
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
+// Older versions stored plain URLs, so both forms are accepted when reading.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredSubscription {
+    Url(String),
+    Full {
+        url: String,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        custom_title: Option<String>,
+        #[serde(default)]
+        folders: Vec<String>,
+        #[serde(default)]
+        refresh_interval: Option<u64>,
+    },
+}
+
+impl From<StoredSubscription> for Subscription {
+    fn from(stored: StoredSubscription) -> Self {
+        match stored {
+            StoredSubscription::Url(url) => Subscription::new(&url),
+            StoredSubscription::Full {
+                url,
+                title,
+                custom_title,
+                folders,
+                refresh_interval,
+            } => Subscription {
+                url,
+                title,
+                custom_title,
+                folders,
+                refresh_interval,
+            },
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+// Styles of the user interface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub unread: Style,
+    pub read: Style,
+    pub selection: Style,
+    pub border: Style,
+    // Border of the pane that receives the navigation keys.
+    pub focused_border: Style,
+    pub popup_border: Style,
+    // Article titles in the popup and the reader.
+    pub title: Style,
+    pub date: Style,
+    pub status_bar: Style,
+    pub error: Style,
+    pub success: Style,
+    // Occurrences of the search query.
+    pub search_match: Style,
+    // Marker of starred articles.
+    pub star: Style,
+    // Inline code and preformatted blocks of article content.
+    pub code: Style,
+    // The `[1]`, `[2]`, ... pointing to the links of article content.
+    pub link_reference: Style,
+    // Removes all colours after rendering, including those of article content.
+    pub monochrome: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            unread: Style::default(),
+            read: Style::default().fg(Color::DarkGray),
+            selection: Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Black)
+                .bg(Color::Gray),
+            border: Style::default(),
+            focused_border: Style::default().fg(Color::Cyan),
+            popup_border: Style::default(),
+            title: Style::default().add_modifier(Modifier::BOLD),
+            date: Style::default(),
+            status_bar: Style::default(),
+            error: Style::default().fg(Color::Red),
+            success: Style::default().fg(Color::Green),
+            search_match: Style::default().fg(Color::Black).bg(Color::Yellow),
+            star: Style::default().fg(Color::Yellow),
+            code: Style::default().fg(Color::Yellow),
+            link_reference: Style::default().fg(Color::Cyan),
+            monochrome: false,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            unread: Style::default().fg(Color::Black),
+            read: Style::default().fg(Color::Gray),
+            selection: Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::White)
+                .bg(Color::Blue),
+            border: Style::default().fg(Color::DarkGray),
+            focused_border: Style::default().fg(Color::Blue),
+            popup_border: Style::default().fg(Color::DarkGray),
+            title: Style::default().add_modifier(Modifier::BOLD),
+            date: Style::default().fg(Color::DarkGray),
+            status_bar: Style::default().fg(Color::DarkGray),
+            error: Style::default().fg(Color::Red),
+            success: Style::default().fg(Color::Green),
+            search_match: Style::default().fg(Color::Black).bg(Color::LightYellow),
+            star: Style::default().fg(Color::Magenta),
+            code: Style::default().fg(Color::Red),
+            link_reference: Style::default().fg(Color::Blue),
+            monochrome: false,
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            unread: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            read: Style::default().fg(Color::Gray),
+            selection: Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Black)
+                .bg(Color::Yellow),
+            border: Style::default().fg(Color::White),
+            focused_border: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            popup_border: Style::default().fg(Color::White),
+            title: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            date: Style::default().fg(Color::White),
+            status_bar: Style::default().fg(Color::Black).bg(Color::White),
+            error: Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD),
+            success: Style::default().fg(Color::LightGreen),
+            search_match: Style::default().fg(Color::Black).bg(Color::LightCyan),
+            star: Style::default()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            code: Style::default().fg(Color::LightYellow),
+            link_reference: Style::default()
+                .fg(Color::LightCyan)
+                .add_modifier(Modifier::BOLD),
+            monochrome: false,
+        }
+    }
+
+    // Only text attributes, for terminals without colours or users who set `NO_COLOR`.
+    pub fn none() -> Self {
+        Self {
+            unread: Style::default(),
+            read: Style::default().add_modifier(Modifier::DIM),
+            selection: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            border: Style::default(),
+            focused_border: Style::default().add_modifier(Modifier::BOLD),
+            popup_border: Style::default(),
+            title: Style::default().add_modifier(Modifier::BOLD),
+            date: Style::default(),
+            status_bar: Style::default(),
+            error: Style::default().add_modifier(Modifier::BOLD),
+            success: Style::default(),
+            search_match: Style::default().add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+            star: Style::default().add_modifier(Modifier::BOLD),
+            code: Style::default(),
+            link_reference: Style::default().add_modifier(Modifier::BOLD),
+            monochrome: true,
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "none" => Some(Self::none()),
+            _ => None,
+        }
+    }
+
+    // Resolves the theme `name`, user-defined themes can be based on any other theme.
+    // Without a configured name `NO_COLOR` selects the `none` theme.
+    pub fn load(
+        name: Option<&str>,
+        themes: &BTreeMap<String, ThemeConfig>,
+    ) -> anyhow::Result<Self> {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let name = match name {
+            Some(name) => name,
+            None if no_color => "none",
+            None => "dark",
+        };
+        Self::resolve(name, themes, 0)
+    }
+
+    fn resolve(
+        name: &str,
+        themes: &BTreeMap<String, ThemeConfig>,
+        depth: usize,
+    ) -> anyhow::Result<Self> {
+        let Some(config) = themes.get(name) else {
+            return Self::builtin(name).ok_or_else(|| anyhow::anyhow!("Unknown theme `{}`", name));
+        };
+        if depth > themes.len() {
+            anyhow::bail!("Theme `{}` is based on a theme that is based on it", name);
+        }
+
+        // A user-defined theme can refine the built-in theme of the same name
+        let base = config.base.as_deref().unwrap_or("dark");
+        let mut theme = match Self::builtin(base) {
+            Some(theme) if base == name => theme,
+            _ => Self::resolve(base, themes, depth + 1)?,
+        };
+        let styles = [
+            (&mut theme.unread, &config.unread),
+            (&mut theme.read, &config.read),
+            (&mut theme.selection, &config.selection),
+            (&mut theme.border, &config.border),
+            (&mut theme.focused_border, &config.focused_border),
+            (&mut theme.popup_border, &config.popup_border),
+            (&mut theme.title, &config.title),
+            (&mut theme.date, &config.date),
+            (&mut theme.status_bar, &config.status_bar),
+            (&mut theme.error, &config.error),
+            (&mut theme.success, &config.success),
+            (&mut theme.search_match, &config.search_match),
+            (&mut theme.star, &config.star),
+            (&mut theme.code, &config.code),
+            (&mut theme.link_reference, &config.link_reference),
+        ];
+        for (style, spec) in styles {
+            if let Some(spec) = spec {
+                *style = spec
+                    .style()
+                    .map_err(|e| anyhow::anyhow!("Theme `{}`: {}", name, e))?;
+            }
+        }
+        Ok(theme)
+    }
+}
+
+// A user-defined theme in the `[themes.<name>]` section of `config.toml`.
+// Styles that aren't set are taken from the `base` theme.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub base: Option<String>,
+    pub unread: Option<StyleConfig>,
+    pub read: Option<StyleConfig>,
+    pub selection: Option<StyleConfig>,
+    pub border: Option<StyleConfig>,
+    pub focused_border: Option<StyleConfig>,
+    pub popup_border: Option<StyleConfig>,
+    pub title: Option<StyleConfig>,
+    pub date: Option<StyleConfig>,
+    pub status_bar: Option<StyleConfig>,
+    pub error: Option<StyleConfig>,
+    pub success: Option<StyleConfig>,
+    pub search_match: Option<StyleConfig>,
+    pub star: Option<StyleConfig>,
+    pub code: Option<StyleConfig>,
+    pub link_reference: Option<StyleConfig>,
+}
+
+// A style like `{ fg = "white", bg = "#005f87", bold = true }`.
+// Colours are names like `red` or `light-blue`, `#rrggbb` values or indexes into the 256 colour palette.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleConfig {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub reversed: bool,
+}
+
+impl StyleConfig {
+    fn style(&self) -> anyhow::Result<Style> {
+        let mut style = Style::default();
+        if let Some(fg) = &self.fg {
+            style = style.fg(parse_color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(parse_color(bg)?);
+        }
+        let modifiers = [
+            (self.bold, Modifier::BOLD),
+            (self.dim, Modifier::DIM),
+            (self.italic, Modifier::ITALIC),
+            (self.underlined, Modifier::UNDERLINED),
+            (self.reversed, Modifier::REVERSED),
+        ];
+        for (enabled, modifier) in modifiers {
+            if enabled {
+                style = style.add_modifier(modifier);
+            }
+        }
+        Ok(style)
+    }
+}
+
+fn parse_color(text: &str) -> anyhow::Result<Color> {
+    Color::from_str(text).map_err(|_| anyhow::anyhow!("Unknown colour `{}`", text))
+}
@@ -1,28 +1,63 @@
 use crate::{
-    app::{App, AppState},
-    feed::Feed,
+    add_feed::{AddFeed, Check},
+    app::{App, AppState, Areas, Focus},
+    article::{Article, Enclosure},
+    download::format_size,
+    feed::FeedStatus,
+    handler::mode,
+    html::LinkReference,
+    keymap::{Action, Mode},
+    reader::{Layout, Reader},
+    search::find_matches,
+    sidebar::Filter,
+    theme::Theme,
 };
 use ratatui::{
-    layout::{Alignment, Rect},
-    style::{Color, Modifier, Style},
-    text::Line,
-    widgets::{block::Title, Block, BorderType, Clear, List, Paragraph, Wrap},
+    layout::{Alignment, Constraint, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{
+        block::Title, Block, BorderType, Cell, Clear, List, ListItem, Paragraph, Row, Table, Wrap,
+    },
     Frame,
 };
 use ratatui_image::StatefulImage;
 
 pub fn render(app: &mut App, frame: &mut Frame) {
+    app.areas = Areas::default();
     let window_area = frame.area();
-    render_list(
-        app,
-        frame,
-        Rect {
-            height: window_area.height - 1,
-            ..window_area
-        },
-    );
-    if app.feeds.is_empty() {
-        render_instructions(frame, window_area);
+    let main_area = Rect {
+        height: window_area.height - 1,
+        ..window_area
+    };
+    if let AppState::Feeds = app.app_state {
+        render_feeds(app, frame, main_area);
+    } else if let AppState::Reader(reader) = &mut app.app_state {
+        render_reader(frame, main_area, reader, &app.config.styles);
+    } else if app.sidebar_visible {
+        let sidebar_width = (main_area.width / 4).clamp(20, 40).min(main_area.width / 2);
+        render_sidebar(
+            app,
+            frame,
+            Rect {
+                width: sidebar_width,
+                ..main_area
+            },
+        );
+        render_list(
+            app,
+            frame,
+            Rect {
+                x: main_area.x + sidebar_width,
+                width: main_area.width - sidebar_width,
+                ..main_area
+            },
+        );
+    } else {
+        render_list(app, frame, main_area);
+    }
+    if app.articles.is_empty() && matches!(app.app_state, AppState::List) {
+        render_instructions(app, frame, window_area);
     }
 
     render_keybindings(
@@ -35,19 +70,36 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         },
     );
 
-    if let AppState::Popup(feed) = &app.app_state {
+    if let AppState::Popup(article) = &app.app_state {
         let popup_area = Rect {
             x: (window_area.width / 2),
             y: window_area.y + 1,
             width: (window_area.width / 2),
             height: window_area.height - 3,
         };
-        render_popup(app, frame, popup_area, feed);
+        app.areas.popup = render_popup(app, frame, popup_area, article);
+    }
+    if let AppState::AddFeed(dialog) = &app.app_state {
+        render_add_feed(frame, window_area, dialog, &app.config.styles);
+    }
+
+    // Colours also come from the content of articles, so they are removed at the very end
+    if app.config.styles.monochrome {
+        for cell in frame.buffer_mut().content.iter_mut() {
+            cell.set_fg(Color::Reset).set_bg(Color::Reset);
+        }
     }
 }
 
-fn render_instructions(frame: &mut Frame<'_>, window_area: Rect) {
-    let instructions = Paragraph::new("Add feeds by running `ta-rss add <url>`")
+fn render_instructions(app: &App, frame: &mut Frame<'_>, window_area: Rect) {
+    let instructions = match app.config.keymap.keys(Mode::List, Action::AddFeed).next() {
+        Some(key) => format!(
+            "Add feeds by pressing `{}`, pasting a URL or running `ta-rss add <url>`",
+            key
+        ),
+        None => "Add feeds by pasting a URL or running `ta-rss add <url>`".to_string(),
+    };
+    let instructions = Paragraph::new(instructions)
         .alignment(Alignment::Center)
         .wrap(Wrap { trim: true });
 
@@ -55,23 +107,284 @@ fn render_instructions(frame: &mut Frame<'_>, window_area: Rect) {
     frame.render_widget(instructions, Rect { y, ..window_area });
 }
 
-fn render_keybindings(_app: &mut App, frame: &mut Frame, area: Rect) {
-    let keybindings = "↑↓: Navigate List | Space: Open Selected Feed | Q: Quit".to_string();
-    frame.render_widget(Line::raw(keybindings), area);
+fn render_keybindings(app: &mut App, frame: &mut Frame, area: Rect) {
+    if let Some(search) = app.search.as_ref().filter(|search| search.editing) {
+        let prompt = format!("/{}", search.query);
+        let cursor_x = area.x + Line::raw(prompt.as_str()).width() as u16;
+        frame.render_widget(Line::styled(prompt, app.config.styles.status_bar), area);
+        frame.set_cursor_position((cursor_x.min(area.right().saturating_sub(1)), area.y));
+        return;
+    }
+    if let AppState::AddFeed(_) = app.app_state {
+        let hints = "Enter: Look for Feeds/Subscribe | ↑/↓: Select Feed | Esc: Cancel";
+        frame.render_widget(Line::styled(hints, app.config.styles.status_bar), area);
+        return;
+    }
+
+    use Action::*;
+    let hints: &[(&[Action], &str)] = match app.app_state {
+        AppState::Feeds => &[
+            (&[SelectPrevious, SelectNext], "Navigate Feeds"),
+            (&[Refresh], "Refresh Feed"),
+            (&[RefreshAll], "Refresh All"),
+            (&[Back], "Back"),
+            (&[Quit], "Quit"),
+        ],
+        AppState::Reader(_) => &[
+            (&[ScrollUp, ScrollDown], "Scroll"),
+            (&[PageUp, PageDown], "Page"),
+            (&[ScrollToTop, ScrollToBottom], "Top/Bottom"),
+            (&[OpenInBrowser], "Open in Browser"),
+            (&[Back], "Back"),
+            (&[Quit], "Quit"),
+        ],
+        AppState::Popup(_) => &[
+            (&[SelectPrevious, SelectNext], "Navigate List"),
+            (&[OpenReader], "Read"),
+            (&[OpenInBrowser], "Open in Browser"),
+            (&[ToggleRead], "Toggle Read"),
+            (&[ToggleStar], "Star"),
+            (&[Back], "Close"),
+            (&[Quit], "Quit"),
+        ],
+        _ if app.search.is_some() => &[
+            (&[NextMatch, PreviousMatch], "Next/Previous Match"),
+            (&[Search], "Edit Search"),
+            (&[Back], "Clear Search"),
+            (&[OpenPopup], "Open Selected Feed"),
+            (&[OpenReader], "Read"),
+            (&[Quit], "Quit"),
+        ],
+        _ if app.focus == Focus::Sidebar => &[
+            (&[SelectPrevious, SelectNext], "Select Feed"),
+            (&[ToggleFocus], "Go To List"),
+            (&[ToggleSidebar], "Hide Sidebar"),
+            (&[Refresh], "Refresh Feed"),
+            (&[OpenFeeds], "Feeds"),
+            (&[AddFeed], "Add Feed"),
+            (&[Quit], "Quit"),
+        ],
+        _ => &[
+            (&[SelectPrevious, SelectNext], "Navigate List"),
+            (&[OpenPopup], "Open Selected Feed"),
+            (&[OpenReader], "Read"),
+            (&[ToggleRead], "Toggle Read"),
+            (&[ToggleStar], "Star"),
+            (&[ToggleFocus], "Sidebar"),
+            (&[RefreshAll], "Refresh All"),
+            (&[OpenFeeds], "Feeds"),
+            (&[Search], "Search"),
+            (&[Quit], "Quit"),
+        ],
+    };
+    let mode = mode(&app.app_state).unwrap_or(Mode::Global);
+    let mut keybindings = Vec::new();
+    for (index, (actions, label)) in hints.iter().enumerate() {
+        // Links are opened with digits, which aren't part of the keymap
+        if index == 3 && matches!(app.app_state, AppState::Reader(_)) {
+            keybindings.push("1-9: Open Link".to_string());
+        }
+        keybindings.extend(app.config.keymap.hint(mode, actions, label));
+    }
+    let keybindings = keybindings.join(" | ");
+    let theme = &app.config.styles;
+    frame.render_widget(Line::styled(keybindings, theme.status_bar), area);
+
+    // Notices, fetches, downloads and failing feeds are shown on the right
+    let mut status = Vec::new();
+    if let Some(notice) = &app.notice {
+        let style = if notice.error {
+            theme.error
+        } else {
+            theme.success
+        };
+        status.push(Span::styled(
+            format!(" {} ", notice.text),
+            theme.status_bar.patch(style),
+        ));
+    }
+    if let Some(progress) = app.fetch_progress.status() {
+        status.push(Span::styled(format!(" {} ", progress), theme.status_bar));
+    }
+    if let Some(downloads) = app.downloads.status() {
+        status.push(Span::styled(format!(" {} ", downloads), theme.status_bar));
+    }
+    let failing = app.failing_feeds();
+    if failing > 0 {
+        status.push(Span::styled(
+            format!(" {} failing ", failing),
+            theme.status_bar.patch(theme.error),
+        ));
+    }
+    if !status.is_empty() {
+        frame.render_widget(Line::from(status).alignment(Alignment::Right), area);
+    }
+}
+
+fn render_feeds(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.styles.clone();
+    let block = Block::bordered()
+        .title("Feeds")
+        .border_style(theme.border)
+        .border_type(BorderType::Rounded);
+
+    let rows = app.subscriptions.iter().map(|subscription| {
+        let status = app.feed_status.get(&subscription.url);
+        let name = app.feed_name(subscription);
+        let (state, style) = match status {
+            Some(FeedStatus {
+                error: Some(error), ..
+            }) => (error.to_string(), theme.error),
+            Some(FeedStatus { fetched: true, .. }) => ("OK".to_string(), theme.success),
+            _ => ("Pending".to_string(), theme.unread),
+        };
+        let last_success = status
+            .map(FeedStatus::last_success_string)
+            .unwrap_or_default();
+
+        Row::new(vec![
+            Cell::from(name.to_string()),
+            Cell::from(state).style(style),
+            Cell::from(last_success).style(theme.date),
+        ])
+        .style(theme.unread)
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(40),
+            Constraint::Fill(1),
+            Constraint::Length(14),
+        ],
+    )
+    .header(Row::new(vec!["Feed", "Status", "Last Success"]).style(theme.title))
+    .highlight_style(theme.selection)
+    .block(block);
+
+    frame.render_stateful_widget(table, area, &mut app.feeds_table_state);
+    app.areas.feeds = area;
 }
 
-fn render_popup(app: &App, frame: &mut Frame, area: Rect, feed: &Feed) {
+// Dialog with the URL to subscribe to and the feeds found there.
+fn render_add_feed(frame: &mut Frame, window_area: Rect, dialog: &AddFeed, theme: &Theme) {
+    let status: Vec<Line> = match &dialog.check {
+        Check::Unchecked if dialog.url.is_empty() => {
+            vec![Line::raw("Type or paste the URL of a feed or website")]
+        }
+        Check::Unchecked => vec![Line::raw("Press Enter to look for feeds")],
+        Check::Checking => vec![Line::raw("Checking…")],
+        Check::Found(candidates) if candidates.len() == 1 => vec![Line::styled(
+            format!("Found: {} (press Enter to subscribe)", candidates[0].title),
+            theme.success,
+        )],
+        Check::Found(candidates) => {
+            let mut lines = vec![Line::styled(
+                "Found several feeds, select one with ↑/↓:",
+                theme.success,
+            )];
+            lines.extend(candidates.iter().enumerate().map(|(index, candidate)| {
+                let line = Line::raw(format!(" {} ({})", candidate.title, candidate.url));
+                if index == dialog.selected {
+                    line.style(theme.selection)
+                } else {
+                    line
+                }
+            }));
+            lines
+        }
+        Check::Failed(error) => vec![Line::styled(error.as_str(), theme.error)],
+    };
+
+    let width = window_area.width.saturating_sub(4).min(72);
+    let height = (status.len() as u16 + 5).min(window_area.height.saturating_sub(1));
+    let area = Rect {
+        x: window_area.x + (window_area.width - width) / 2,
+        y: window_area.y + window_area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    let block = Block::bordered()
+        .title(" Add Feed ")
+        .border_style(theme.popup_border)
+        .border_type(BorderType::Rounded);
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    // Long URLs are scrolled so that their end stays visible
+    let url_width = Line::raw(dialog.url.as_str()).width() as u16;
+    let input_width = inner.width.saturating_sub(1);
+    let offset = url_width.saturating_sub(input_width);
+    frame.render_widget(
+        Paragraph::new(dialog.url.as_str()).scroll((0, offset)),
+        Rect { height: 1, ..inner },
+    );
+    frame.set_cursor_position((inner.x + url_width - offset, inner.y));
+
+    // A long list of feeds scrolls with the selection, other messages are wrapped
+    let status_area = Rect {
+        y: inner.y + 2,
+        height: inner.height.saturating_sub(2),
+        ..inner
+    };
+    let status = match &dialog.check {
+        Check::Found(candidates) if candidates.len() > 1 => {
+            let scroll = (dialog.selected as u16 + 2).saturating_sub(status_area.height);
+            Paragraph::new(status).scroll((scroll, 0))
+        }
+        _ => Paragraph::new(status).wrap(Wrap { trim: true }),
+    };
+    frame.render_widget(status, status_area);
+}
+
+// Renders the popup and returns the area it covers.
+fn render_popup(app: &App, frame: &mut Frame, area: Rect, article: &Article) -> Rect {
     // Extract and convert relevant data
-    let date = feed.pub_date_string();
+    let date = article.date_string();
     let source = {
-        let mut source = feed.source_name();
-        let source_len = area.width as usize - (date.len() + 4);
-        source.truncate(source_len);
-        source
+        let mut source = article.feed_title.clone();
+        if !article.authors.is_empty() {
+            source = format!("{} - {}", source, article.authors.join(", "));
+        }
+        let source_len = (area.width as usize).saturating_sub(date.len() + 4);
+        source.chars().take(source_len).collect::<String>()
     };
-    let title = Paragraph::new(feed.title()).wrap(Wrap { trim: true });
-    let description = Paragraph::new(feed.description()).wrap(Wrap { trim: true });
+    let theme = &app.config.styles;
+    let title = Paragraph::new(article.title.as_str())
+        .style(theme.title)
+        .wrap(Wrap { trim: true });
+    let description =
+        Paragraph::new(app.current_description.text.clone()).wrap(Wrap { trim: false });
     let image = &app.current_feed_image;
+    let mut actions = vec![
+        (Action::OpenReader, "Read"),
+        (Action::OpenInBrowser, "Open in Browser"),
+        (
+            Action::ToggleStar,
+            if app.is_starred(article) {
+                "Unstar"
+            } else {
+                "Star"
+            },
+        ),
+    ];
+    if article.media_enclosure().is_some() {
+        actions.extend([
+            (Action::PlayEnclosure, "Play"),
+            (Action::DownloadEnclosure, "Download"),
+        ]);
+    }
+    let hints: Vec<String> = actions
+        .iter()
+        .filter_map(|&(action, label)| app.config.keymap.hint(Mode::Popup, &[action], label))
+        .collect();
+    let enclosures: Vec<Line> = article
+        .enclosures
+        .iter()
+        .filter(|enclosure| enclosure.is_media())
+        .map(|enclosure| enclosure_line(app, article, enclosure))
+        .collect();
 
     // Set-up layout
     let title_area = Rect {
@@ -97,7 +410,12 @@ fn render_popup(app: &App, frame: &mut Frame, area: Rect, feed: &Feed) {
         height: 9,
     };
 
-    let popup_height = title_area.height + description_area.height + image_area.height + 6;
+    let enclosures_height = match enclosures.len() as u16 {
+        0 => 0,
+        lines => lines + 1,
+    };
+    let popup_height =
+        title_area.height + description_area.height + image_area.height + enclosures_height + 6;
     let popup_area = Rect {
         height: popup_height,
         ..area
@@ -105,8 +423,9 @@ fn render_popup(app: &App, frame: &mut Frame, area: Rect, feed: &Feed) {
 
     // Render everything
     let block = Block::bordered()
+        .border_style(theme.popup_border)
         .title(source)
-        .title(Title::from(date).alignment(Alignment::Right));
+        .title(Title::from(Span::styled(date, theme.date)).alignment(Alignment::Right));
     // Clear the popup window
     frame.render_widget(Clear, popup_area);
     frame.render_widget(block, popup_area);
@@ -129,32 +448,247 @@ fn render_popup(app: &App, frame: &mut Frame, area: Rect, feed: &Feed) {
             ..description_area
         },
     );
+    frame.render_widget(
+        Paragraph::new(enclosures),
+        Rect {
+            y: y_coordinate + description_area.height + 1,
+            height: enclosures_height.saturating_sub(1),
+            ..description_area
+        },
+    );
 
     frame.render_widget(
-        Paragraph::new(" O: Open in Browser ").alignment(Alignment::Right),
+        Paragraph::new(format!(" {} ", hints.join(" | "))).alignment(Alignment::Right),
         Rect {
             y: popup_area.y + popup_area.height - 1,
             height: 2,
             ..description_area
         },
-    )
+    );
+    popup_area
 }
 
-fn render_list(app: &mut App, frame: &mut Frame, area: Rect) {
+// Line like `▶ episode.mp3 · audio/mpeg · 24.1 MB · 45:12` with the state of its download.
+fn enclosure_line(app: &App, article: &Article, enclosure: &Enclosure) -> Line<'static> {
+    let mut parts = vec![enclosure.file_name()];
+    parts.extend(enclosure.mime_type.clone());
+    parts.extend(enclosure.length.map(format_size));
+    parts.extend(enclosure.duration.clone());
+    let mut line = Line::from(format!("▶ {}", parts.join(" · ")));
+
+    let theme = &app.config.styles;
+    if let Some(download) = app.downloads.active.get(&enclosure.url) {
+        let progress = match download.percent() {
+            Some(percent) => format!(" (downloading {}%)", percent),
+            None => " (downloading)".to_string(),
+        };
+        line.push_span(Span::styled(progress, theme.date));
+    } else if app.enclosure_path(&article.feed_title, enclosure).is_file() {
+        line.push_span(Span::styled(" (downloaded)", theme.success));
+    }
+    line
+}
+
+fn render_reader(frame: &mut Frame, area: Rect, reader: &mut Reader, theme: &Theme) {
+    let article = &reader.article;
     let block = Block::bordered()
-        .title("Ta-RSS")
-        .border_style(Style::default())
-        .border_type(BorderType::Rounded)
-        .style(Style::default());
-
-    let feed_list: List = List::new(app.feeds.iter().map(|feed| feed.title().clone()))
-        .highlight_style(
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .fg(Color::Black)
-                .bg(Color::Gray),
+        .border_style(theme.popup_border)
+        .title(article.feed_title.as_str())
+        .title(
+            Title::from(Span::styled(article.date_string(), theme.date))
+                .alignment(Alignment::Right),
         )
+        .title_bottom(Line::from(format!(" {} ", reader.position())).alignment(Alignment::Right))
+        .border_type(BorderType::Rounded);
+    let inner = block.inner(area);
+    let content_area = Rect {
+        x: inner.x + 1,
+        width: inner.width.saturating_sub(2),
+        ..inner
+    };
+
+    // Wrapping only has to be redone when the width changes
+    if reader.layout.width != content_area.width || reader.layout.lines.is_empty() {
+        let mut lines = vec![Line::styled(article.title.clone(), theme.title)];
+        if !article.authors.is_empty() {
+            lines.push(Line::styled(article.authors.join(", "), theme.date));
+        }
+        lines.push(Line::default());
+        let header = lines.len();
+        lines.extend(reader.document.text.lines.iter().cloned());
+        let references: Vec<LinkReference> = reader
+            .document
+            .references
+            .iter()
+            .map(|&reference| LinkReference {
+                line: reference.line + header,
+                ..reference
+            })
+            .collect();
+        reader.layout = Layout::wrap(&lines, &references, content_area.width);
+    }
+
+    // The scroll limits depend on how the content wraps at the current size
+    reader.content_height = reader.layout.lines.len();
+    reader.page_height = content_area.height as usize;
+    reader.scroll = reader.scroll.min(reader.max_scroll());
+
+    let visible = reader.scroll..(reader.scroll + reader.page_height).min(reader.content_height);
+    let content = Paragraph::new(reader.layout.lines[visible.clone()].to_vec());
+    frame.render_widget(block, area);
+    frame.render_widget(content, content_area);
+
+    // Remember where the link references ended up, so they can be clicked
+    reader.link_areas = reader
+        .layout
+        .references
+        .iter()
+        .filter(|(row, ..)| visible.contains(row))
+        .map(|&(row, column, width, link)| {
+            let y = content_area.y + (row - visible.start) as u16;
+            (Rect::new(content_area.x + column, y, width, 1), link)
+        })
+        .collect();
+}
+
+fn render_sidebar(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.styles.clone();
+    let block = Block::bordered()
+        .title("Feeds")
+        .border_style(border_style(&theme, app.focus == Focus::Sidebar))
+        .border_type(BorderType::Rounded);
+
+    let unread_counts = app.unread_counts();
+    let entry = |depth: usize, name: &str, unread: usize, failing: bool| {
+        let indent = "  ".repeat(depth);
+        let marker = if failing { "! " } else { "" };
+        let text = match unread {
+            0 => format!("{}{}{}", indent, marker, name),
+            unread => format!("{}{}{} ({})", indent, marker, name, unread),
+        };
+        let style = match (failing, unread) {
+            (true, _) => theme.error,
+            (false, 0) => theme.read,
+            _ => theme.unread,
+        };
+        ListItem::new(text).style(style)
+    };
+
+    let items = app.sidebar.iter().map(|sidebar_entry| {
+        let depth = sidebar_entry.depth;
+        match &sidebar_entry.filter {
+            None => entry(depth, "All items", unread_counts.values().sum(), false),
+            Some(Filter::Starred) => {
+                let unread = app
+                    .articles
+                    .iter()
+                    .filter(|article| app.is_starred(article) && !app.is_read(article))
+                    .count();
+                entry(depth, "★ Starred", unread, false)
+            }
+            Some(filter @ Filter::Folder(path)) => {
+                let urls = app.filter_urls(filter);
+                let unread = urls
+                    .iter()
+                    .filter_map(|url| unread_counts.get(url.as_str()))
+                    .sum();
+                let failing = urls.iter().any(|url| {
+                    app.feed_status
+                        .get(url)
+                        .is_some_and(|status| status.error.is_some())
+                });
+                let name = path.rsplit('/').next().unwrap_or(path);
+                entry(depth, &format!("▾ {}", name), unread, failing)
+            }
+            Some(Filter::Feed(url)) => {
+                let Some(subscription) = app
+                    .subscriptions
+                    .iter()
+                    .find(|subscription| subscription.url == *url)
+                else {
+                    return entry(depth, url, 0, false);
+                };
+                let unread = unread_counts.get(url.as_str()).copied().unwrap_or_default();
+                let failing = app
+                    .feed_status
+                    .get(url)
+                    .is_some_and(|status| status.error.is_some());
+                entry(depth, app.feed_name(subscription), unread, failing)
+            }
+        }
+    });
+
+    let sidebar = List::new(items)
+        .highlight_style(theme.selection)
+        .block(block);
+
+    frame.render_stateful_widget(sidebar, area, &mut app.sidebar_state);
+    app.areas.sidebar = area;
+}
+
+fn border_style(theme: &Theme, focused: bool) -> Style {
+    if focused {
+        theme.focused_border
+    } else {
+        theme.border
+    }
+}
+
+fn render_list(app: &mut App, frame: &mut Frame, area: Rect) {
+    let title = match &app.search {
+        Some(search) if !search.query.is_empty() => format!(
+            "Ta-RSS - {} matching \"{}\"",
+            app.visible.len(),
+            search.query
+        ),
+        _ => "Ta-RSS".to_string(),
+    };
+    let theme = app.config.styles.clone();
+    let block = Block::bordered()
+        .title(title)
+        .border_style(border_style(
+            &theme,
+            app.sidebar_visible && app.focus == Focus::List,
+        ))
+        .border_type(BorderType::Rounded);
+
+    let (articles, read_articles, starred_articles) =
+        (&app.articles, &app.read_articles, &app.starred_articles);
+    let query = app
+        .search
+        .as_ref()
+        .map_or("", |search| search.query.as_str());
+    let items = app.visible.iter().map(|&index| {
+        let article = &articles[index];
+        let style = if read_articles.contains(article) {
+            theme.read
+        } else {
+            theme.unread
+        };
+        let mut line = highlight_matches(&article.title, query, theme.search_match);
+        if starred_articles.contains(article) {
+            line.spans.insert(0, Span::styled("★ ", theme.star));
+        }
+        ListItem::new(line).style(style)
+    });
+
+    let feed_list: List = List::new(items)
+        .highlight_style(theme.selection)
         .block(block);
 
     frame.render_stateful_widget(feed_list, area, &mut app.list_state);
+    app.areas.list = area;
+}
+
+// Splits the text into spans with the occurrences of the query highlighted.
+fn highlight_matches<'a>(text: &'a str, query: &str, style: Style) -> Line<'a> {
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for range in find_matches(text, query) {
+        spans.push(Span::raw(&text[end..range.start]));
+        spans.push(Span::styled(&text[range.clone()], style));
+        end = range.end;
+    }
+    spans.push(Span::raw(&text[end..]));
+    Line::from(spans)
 }